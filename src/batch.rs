@@ -0,0 +1,150 @@
+//! `statx()` over many paths in parallel.
+
+use crate::{statx, with_c_path, StatxError, StatxRequest, AT_FDCWD};
+use core::mem::{self, MaybeUninit};
+use std::num::NonZeroUsize;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::thread;
+use std::vec::Vec;
+
+/// Upper bound of the default number of threads.
+const MAX_THREADS: usize = 16;
+/// Minimum number of paths per thread, below which spawning is not worth it.
+const MIN_PATHS_PER_THREAD: usize = 32;
+
+/// Run `request` on every path, with as many threads as available cores, up to
+/// 16.
+///
+/// Results are in the order of `paths`, with one error per failed path.
+///
+/// The number of threads is bounded, but they are not a pool: scoped threads
+/// are spawned anew on each call and joined before returning.
+pub fn statx_many<P>(paths: &[P], request: &StatxRequest) -> Vec<Result<statx, StatxError>>
+where
+    P: AsRef<Path> + Sync,
+{
+    statx_many_with_threads(paths, request, default_threads())
+}
+
+/// Like `statx_many`, with at most `max_threads` threads.
+///
+/// The paths are split into contiguous chunks, one per thread. Each thread
+/// reuses a single `statx` buffer, which the kernel overwrites entirely, and
+/// moves it into the result. With one thread or few paths, everything runs on
+/// the current thread.
+pub fn statx_many_with_threads<P>(
+    paths: &[P],
+    request: &StatxRequest,
+    max_threads: usize,
+) -> Vec<Result<statx, StatxError>>
+where
+    P: AsRef<Path> + Sync,
+{
+    let mut out = Vec::with_capacity(paths.len());
+    run_many(
+        paths,
+        request,
+        &mut out.spare_capacity_mut()[..paths.len()],
+        max_threads,
+        fill_chunk,
+    );
+    // Every slot was written, or a panic was resumed.
+    unsafe { out.set_len(paths.len()) };
+    out
+}
+
+/// Like `statx_many`, writing the result of `paths[i]` to `out[i]`.
+///
+/// The kernel writes straight into the `statx` of `Ok` slots, so reusing `out`
+/// across calls saves copying every result. `Err` slots are filled from the
+/// buffer of the thread.
+///
+/// # Panics
+///
+/// If `out` and `paths` have different lengths.
+pub fn statx_many_into<P>(
+    paths: &[P],
+    request: &StatxRequest,
+    out: &mut [Result<statx, StatxError>],
+) where
+    P: AsRef<Path> + Sync,
+{
+    assert_eq!(paths.len(), out.len(), "one output slot per path");
+    run_many(paths, request, out, default_threads(), update_chunk);
+}
+
+fn default_threads() -> usize {
+    thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(MAX_THREADS)
+}
+
+/// Run `run_chunk` over contiguous chunks of `paths` and `out`.
+fn run_many<P, T>(
+    paths: &[P],
+    request: &StatxRequest,
+    out: &mut [T],
+    max_threads: usize,
+    run_chunk: fn(&[P], &StatxRequest, &mut [T]),
+) where
+    P: AsRef<Path> + Sync,
+    T: Send,
+{
+    let threads = max_threads.min(paths.len() / MIN_PATHS_PER_THREAD).max(1);
+    if threads == 1 {
+        return run_chunk(paths, request, out);
+    }
+
+    let chunk_len = paths.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let handles: Vec<_> = paths
+            .chunks(chunk_len)
+            .zip(out.chunks_mut(chunk_len))
+            .map(|(paths, out)| scope.spawn(move || run_chunk(paths, request, out)))
+            .collect();
+        for handle in handles {
+            if let Err(panic) = handle.join() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+    })
+}
+
+/// `statx()` of `path` into `buf`.
+fn run_one<P: AsRef<Path>>(
+    path: &P,
+    request: &StatxRequest,
+    buf: &mut statx,
+) -> Result<(), StatxError> {
+    let path = path.as_ref().as_os_str().as_bytes();
+    with_c_path(path, |path| request.at_into(AT_FDCWD, path, buf))
+}
+
+fn fill_chunk<P: AsRef<Path>>(
+    paths: &[P],
+    request: &StatxRequest,
+    out: &mut [MaybeUninit<Result<statx, StatxError>>],
+) {
+    let mut buf = unsafe { mem::zeroed::<statx>() };
+    for (path, slot) in paths.iter().zip(out) {
+        slot.write(run_one(path, request, &mut buf).map(|()| buf));
+    }
+}
+
+fn update_chunk<P: AsRef<Path>>(
+    paths: &[P],
+    request: &StatxRequest,
+    out: &mut [Result<statx, StatxError>],
+) {
+    let mut buf = unsafe { mem::zeroed::<statx>() };
+    for (path, slot) in paths.iter().zip(out) {
+        let result = match slot {
+            Ok(in_place) => run_one(path, request, in_place),
+            Err(_) => run_one(path, request, &mut buf).map(|()| *slot = Ok(buf)),
+        };
+        if let Err(err) = result {
+            *slot = Err(err);
+        }
+    }
+}
@@ -0,0 +1,492 @@
+//! `statx` command, like coreutils `stat` but showing everything `statx()`
+//! can report.
+
+use statx_sys::*;
+use std::env;
+use std::ffi::OsString;
+use std::fmt::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "\
+Usage: statx [OPTION]... FILE...
+Display file status as reported by statx(2). FILE `-` is standard input.
+
+  -L, --dereference     follow symlinks
+  -c, --format=FORMAT   use FORMAT instead of the default, with a newline after
+                        each file
+      --printf=FORMAT   like --format, but interpret backslash escapes and do
+                        not output a trailing newline
+      --sync            synchronise with the server first (AT_STATX_FORCE_SYNC)
+      --no-sync         use cached attributes only (AT_STATX_DONT_SYNC)
+      --mask=LIST       fields to request, as a comma-separated list of names
+                        (basic_stats, all, type, mode, ..., btime, mnt_id,
+                        dioalign, mnt_id_unique, subvol, write_atomic,
+                        dio_read_align) or a number
+  -h, --help            display this help and exit
+
+Format directives, `?` is printed for fields the kernel did not fill in:
+  %a  permission bits in octal        %A  permissions like `ls -l`
+  %b  number of blocks allocated      %B  size in bytes of each block (512)
+  %d  device number in decimal        %D  device number in hex
+  %Hd major device number             %Ld minor device number
+  %f  raw mode in hex                 %F  file type
+  %g  group ID of owner               %h  number of hard links
+  %i  inode number                    %n  file name
+  %N  quoted file name, with the target of symlinks
+  %o  optimal I/O transfer size       %s  total size in bytes
+  %t  major device type in hex        %T  minor device type in hex
+  %Hr major device type               %Lr minor device type
+  %u  user ID of owner
+  %w  time of birth, or `-`           %W  time of birth in seconds, or 0
+  %x  time of last access             %X  time of last access in seconds
+  %y  time of last modification       %Y  time of last modification in seconds
+  %z  time of last change             %Z  time of last change in seconds
+  %M  mount ID                        %v  subvolume ID
+  %K  file attributes, comma-separated, or `-`
+  %j  direct I/O memory alignment     %J  direct I/O offset alignment
+  %q  atomic write unit min-max, or `-`
+  %m  mask of the filled in fields, in hex
+
+Times are displayed in UTC.";
+
+const DEFAULT_FORMAT: &str = "  File: %N
+  Size: %-10s\tBlocks: %-10b IO Block: %-6o %F
+Device: %Hd,%Ld\tInode: %-11i Links: %-5h Device type: %Hr,%Lr
+Access: (%04a/%A)  Uid: %-5u  Gid: %-5g
+Access: %x
+Modify: %y
+Change: %z
+ Birth: %w
+ Mount: %-10M Subvol: %v
+ Attrs: %K
+   DIO: mem %j, offset %J\tAtomic write: %q";
+
+/// Parsed command line.
+struct Options {
+    request: StatxRequest,
+    format: Option<String>,
+    escapes: bool,
+    files: Vec<OsString>,
+    help: bool,
+}
+
+/// Parse the arguments, with options allowed anywhere before `--`.
+fn parse_args(mut args: impl Iterator<Item = OsString>) -> Result<Options, String> {
+    let mut options = Options {
+        request: StatxRequest::new()
+            .mask(default_mask())
+            .follow_symlinks(false),
+        format: None,
+        escapes: false,
+        files: Vec::new(),
+        help: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let arg = match arg.to_str() {
+            Some(arg) if arg.starts_with('-') && arg != "-" => arg.to_owned(),
+            _ => {
+                options.files.push(arg);
+                continue;
+            }
+        };
+        let (name, value) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => (&arg[..i], Some(arg[i + 1..].to_owned())),
+            _ if arg.starts_with("-c") && arg.len() > 2 => ("-c", Some(arg[2..].to_owned())),
+            _ => (&*arg, None),
+        };
+        let mut value = || {
+            value
+                .clone()
+                .or_else(|| args.next().and_then(|v| v.into_string().ok()))
+                .ok_or_else(|| format!("option `{}` requires a value", name))
+        };
+        match name {
+            "-L" | "--dereference" => options.request = options.request.follow_symlinks(true),
+            "-c" | "--format" => {
+                options.format = Some(value()?);
+                options.escapes = false;
+            }
+            "--printf" => {
+                options.format = Some(value()?);
+                options.escapes = true;
+            }
+            "--sync" => options.request = options.request.sync(SyncMode::ForceSync),
+            "--no-sync" => options.request = options.request.sync(SyncMode::DontSync),
+            "--mask" => {
+                let list = value()?;
+                let mask = parse_mask(&list).ok_or_else(|| format!("invalid mask `{}`", list))?;
+                options.request = options.request.mask(mask);
+            }
+            "-h" | "--help" => options.help = true,
+            "--" => options.files.extend(args.by_ref()),
+            _ => return Err(format!("unknown option `{}`", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn main() {
+    let options = parse_args(env::args_os().skip(1)).unwrap_or_else(|msg| usage_error(&msg));
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
+    if options.files.is_empty() {
+        usage_error("missing operand");
+    }
+    let Options {
+        request,
+        format,
+        escapes,
+        files,
+        ..
+    } = options;
+
+    let mut failed = false;
+    for file in &files {
+        // `-` is standard input, as with coreutils `stat`.
+        let result = if file == "-" {
+            request.fd(libc::STDIN_FILENO)
+        } else {
+            request.path_bytes(file.as_bytes())
+        };
+        match result {
+            Ok(buf) => {
+                let out = match &format {
+                    Some(format) if escapes => render(&unescape(format), file, &buf),
+                    Some(format) => render(format, file, &buf) + "\n",
+                    None => render(DEFAULT_FORMAT, file, &buf) + "\n",
+                };
+                print!("{}", out);
+            }
+            Err(err) => {
+                eprintln!(
+                    "statx: cannot statx '{}': {}",
+                    Path::new(file).display(),
+                    err
+                );
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+fn usage_error(msg: &str) -> ! {
+    eprintln!("statx: {}\nTry 'statx --help' for more information.", msg);
+    process::exit(1);
+}
+
+fn default_mask() -> StatxMask {
+    StatxMask::ALL
+        | StatxMask::MNT_ID
+        | StatxMask::DIOALIGN
+        | StatxMask::SUBVOL
+        | StatxMask::WRITE_ATOMIC
+        | StatxMask::DIO_READ_ALIGN
+}
+
+/// Parse `basic_stats,btime`, `all`, `0x1fff` or `8191`.
+fn parse_mask(list: &str) -> Option<StatxMask> {
+    let number = match list.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => list.parse().ok(),
+    };
+    if let Some(bits) = number {
+        return StatxMask::from_bits(bits);
+    }
+    list.split(',').try_fold(StatxMask::empty(), |mask, name| {
+        let bits = match name.trim().to_ascii_lowercase().as_str() {
+            "basic_stats" => StatxMask::BASIC_STATS,
+            "all" => StatxMask::ALL,
+            name => StatxMask::from_name(name)?,
+        };
+        Some(mask | bits)
+    })
+}
+
+/// Interpret the backslash escapes of `--printf`.
+fn unescape(format: &str) -> String {
+    let mut out = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('0') => out.push('\0'),
+            Some('\\') => out.push('\\'),
+            Some('"') => out.push('"'),
+            Some(c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Expand the `%` directives of `format` for `file`.
+fn render(format: &str, file: &OsString, buf: &statx) -> String {
+    let mut out = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+
+        let mut spec = String::from("%");
+        let (mut left, mut zero) = (false, false);
+        while let Some(&flag @ ('-' | '0')) = chars.peek() {
+            left |= flag == '-';
+            zero |= flag == '0';
+            spec.push(flag);
+            chars.next();
+        }
+        let mut width = 0;
+        while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+            width = width * 10 + digit as usize;
+            spec.push(chars.next().unwrap());
+        }
+        let mut directive = match chars.next() {
+            Some(c) => c.to_string(),
+            None => {
+                out.push_str(&spec);
+                break;
+            }
+        };
+        if directive == "H" || directive == "L" {
+            if let Some(&next @ ('d' | 'r')) = chars.peek() {
+                directive.push(next);
+                chars.next();
+            }
+        }
+
+        let value = match expand(&directive, file, buf) {
+            Some(value) => value,
+            None => {
+                out.push_str(&spec);
+                out.push_str(&directive);
+                continue;
+            }
+        };
+        let pad = width.saturating_sub(value.chars().count());
+        let numeric = value.bytes().all(|b| b.is_ascii_digit());
+        if left {
+            out.push_str(&value);
+            out.extend((0..pad).map(|_| ' '));
+        } else {
+            let fill = if zero && numeric { '0' } else { ' ' };
+            out.extend((0..pad).map(|_| fill));
+            out.push_str(&value);
+        }
+    }
+    out
+}
+
+/// Value of one directive, or `None` if unknown.
+fn expand(directive: &str, file: &OsString, buf: &statx) -> Option<String> {
+    fn or_unknown<T: ToString>(value: Option<T>) -> String {
+        value.map_or_else(|| "?".to_owned(), |v| v.to_string())
+    }
+
+    let value = match directive {
+        "%" => "%".to_owned(),
+        "a" => or_unknown(buf.permissions().map(|p| format!("{:o}", p.bits()))),
+        "A" => or_unknown(buf.file_mode()),
+        "b" => or_unknown(buf.blocks()),
+        "B" => "512".to_owned(),
+        "d" => buf.dev().to_string(),
+        "D" => format!("{:x}", buf.dev()),
+        "Hd" => buf.stx_dev_major.to_string(),
+        "Ld" => buf.stx_dev_minor.to_string(),
+        "f" => or_unknown(buf.mode().map(|mode| format!("{:x}", mode))),
+        "F" => or_unknown(buf.file_type().map(|t| file_type_name(t, buf.size()))),
+        "g" => or_unknown(buf.gid()),
+        "h" => or_unknown(buf.nlink()),
+        "i" => or_unknown(buf.ino()),
+        "n" => Path::new(file).display().to_string(),
+        "N" => quoted_name(file, buf),
+        "o" => buf.blksize().to_string(),
+        "s" => or_unknown(buf.size()),
+        "t" => format!("{:x}", buf.stx_rdev_major),
+        "T" => format!("{:x}", buf.stx_rdev_minor),
+        "Hr" => buf.stx_rdev_major.to_string(),
+        "Lr" => buf.stx_rdev_minor.to_string(),
+        "u" => or_unknown(buf.uid()),
+        "w" => buf.btime().map_or_else(|| "-".to_owned(), human_time),
+        "W" => buf
+            .btime()
+            .map_or_else(|| "0".to_owned(), |t| t.tv_sec.to_string()),
+        "x" => or_unknown(buf.atime().map(human_time)),
+        "X" => or_unknown(buf.atime().map(|t| t.tv_sec)),
+        "y" => or_unknown(buf.mtime().map(human_time)),
+        "Y" => or_unknown(buf.mtime().map(|t| t.tv_sec)),
+        "z" => or_unknown(buf.ctime().map(human_time)),
+        "Z" => or_unknown(buf.ctime().map(|t| t.tv_sec)),
+        "M" => or_unknown(buf.mnt_id().map(|id| id.get())),
+        "v" => or_unknown(buf.subvol()),
+        "K" => attributes(buf),
+        "j" => or_unknown(buf.dio_alignment().map(|a| a.mem_align)),
+        "J" => or_unknown(buf.dio_alignment().map(|a| a.offset_align)),
+        "q" => buf.atomic_write_limits().map_or_else(
+            || "-".to_owned(),
+            |l| format!("{}-{}", l.unit_min, l.unit_max),
+        ),
+        "m" => format!("{:x}", buf.stx_mask),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn file_type_name(file_type: FileType, size: Option<u64>) -> &'static str {
+    match file_type {
+        FileType::Regular if size == Some(0) => "regular empty file",
+        FileType::Regular => "regular file",
+        FileType::Directory => "directory",
+        FileType::Symlink => "symbolic link",
+        FileType::BlockDevice => "block special file",
+        FileType::CharDevice => "character special file",
+        FileType::Fifo => "fifo",
+        FileType::Socket => "socket",
+        FileType::Unknown => "weird file",
+    }
+}
+
+fn quoted_name(file: &OsString, buf: &statx) -> String {
+    let path = Path::new(file);
+    let mut out = format!("'{}'", path.display());
+    if buf.file_type() == Some(FileType::Symlink) {
+        if let Ok(target) = std::fs::read_link(path) {
+            let _ = write!(out, " -> '{}'", target.display());
+        }
+    }
+    out
+}
+
+fn attributes(buf: &statx) -> String {
+    let names: Vec<String> = buf
+        .attributes()
+        .iter()
+        .map(|attr| match attr.name() {
+            Some(name) => name.to_ascii_lowercase(),
+            None => format!("{:#x}", attr.bits()),
+        })
+        .collect();
+    if names.is_empty() {
+        "-".to_owned()
+    } else {
+        names.join(",")
+    }
+}
+
+/// `2024-01-31 12:34:56.123456789 +0000`.
+fn human_time(ts: statx_timestamp) -> String {
+    let days = ts.tv_sec.div_euclid(86400);
+    let secs = ts.tv_sec.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:09} +0000",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        ts.tc_nsec
+    )
+}
+
+/// Date of a number of days since 1970-01-01 in the proleptic Gregorian
+/// calendar, from http://howardhinnant.github.io/date_algorithms.html.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month as u32, day as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::zeroed;
+
+    fn sample() -> statx {
+        let mut buf = unsafe { zeroed::<statx>() };
+        buf.stx_mask = STATX_BASIC_STATS | STATX_MNT_ID;
+        buf.stx_mode = 0o042755;
+        buf.stx_size = 4096;
+        buf.stx_uid = 1000;
+        buf.stx_mnt_id = 42;
+        buf.stx_dev_major = 259;
+        buf.stx_dev_minor = 3;
+        buf.stx_mtime = statx_timestamp::new(1_700_000_000, 5).unwrap();
+        buf.stx_attributes = STATX_ATTR_IMMUTABLE | STATX_ATTR_APPEND;
+        buf
+    }
+
+    #[test]
+    fn directives() {
+        let file = OsString::from("dir");
+        let render = |format| render(format, &file, &sample());
+        assert_eq!(render("%n %F %A %04a"), "dir directory drwxr-sr-x 2755");
+        assert_eq!(render("%Hd:%Ld %M %u"), "259:3 42 1000");
+        assert_eq!(render("[%-6s|%6s|%06s]"), "[4096  |  4096|004096]");
+        assert_eq!(render("%w %W %K"), "- 0 immutable,append");
+        assert_eq!(render("%y"), "2023-11-14 22:13:20.000000005 +0000");
+        assert_eq!(render("%v %% %Q"), "? % %Q");
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        let ts = statx_timestamp::new(-1, 500_000_000).unwrap();
+        assert_eq!(human_time(ts), "1969-12-31 23:59:59.500000000 +0000");
+    }
+
+    #[test]
+    fn args() {
+        let parse = |args: &[&str]| parse_args(args.iter().map(OsString::from));
+        let options = parse(&["Cargo.toml", "-L", "-c%n %s", "src"]).unwrap();
+        assert_eq!(options.files, ["Cargo.toml", "src"]);
+        assert_eq!(options.format.as_deref(), Some("%n %s"));
+        assert!(options.request.flags_bits() & AT_SYMLINK_NOFOLLOW as i32 == 0);
+
+        let options = parse(&["--printf=%i\\n", "--", "-L", "-"]).unwrap();
+        assert_eq!(options.files, ["-L", "-"]);
+        assert!(options.escapes);
+        assert!(options.request.flags_bits() & AT_SYMLINK_NOFOLLOW as i32 != 0);
+
+        assert!(parse(&["Cargo.toml", "-x"]).is_err());
+        assert!(parse(&["Cargo.toml", "--format"]).is_err());
+    }
+
+    #[test]
+    fn masks() {
+        let mask = parse_mask("basic_stats,btime,MNT_ID").unwrap();
+        assert_eq!(mask, StatxMask::ALL | StatxMask::MNT_ID);
+        assert_eq!(parse_mask("0x800"), Some(StatxMask::BTIME));
+        assert_eq!(parse_mask("0x80000000"), None);
+        assert_eq!(parse_mask("btime,nope"), None);
+        assert_eq!(unescape("a\\tb\\n"), "a\tb\n");
+    }
+}
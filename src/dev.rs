@@ -0,0 +1,147 @@
+//! Device numbers.
+
+use crate::statx;
+use core::fmt;
+use core::str::FromStr;
+
+/// Compose a `dev_t` from major and minor numbers, with the glibc layout
+/// `MMMM_Mmmm_mmmM_MMmm` also used by musl, bionic and `std`.
+pub const fn makedev(major: u32, minor: u32) -> u64 {
+    let (major, minor) = (major as u64, minor as u64);
+    ((major & 0xffff_f000) << 32)
+        | ((major & 0x0000_0fff) << 8)
+        | ((minor & 0xffff_ff00) << 12)
+        | (minor & 0x0000_00ff)
+}
+
+/// Major number of a `dev_t`, see `makedev`.
+pub const fn major(dev: u64) -> u32 {
+    (((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0x0000_0fff)) as u32
+}
+
+/// Minor number of a `dev_t`, see `makedev`.
+pub const fn minor(dev: u64) -> u32 {
+    (((dev >> 12) & 0xffff_ff00) | (dev & 0x0000_00ff)) as u32
+}
+
+/// A device number, split into major and minor.
+///
+/// Displayed and parsed as `MAJ:MIN`, like in `/proc/self/mountinfo` and
+/// `/sys/dev`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeviceId {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl DeviceId {
+    /// Create a device number from its major and minor parts.
+    pub const fn new(major: u32, minor: u32) -> Self {
+        DeviceId { major, minor }
+    }
+
+    /// Decode a `dev_t`, e.g. from `MetadataExt::dev`.
+    pub const fn from_dev(dev: u64) -> Self {
+        DeviceId::new(major(dev), minor(dev))
+    }
+
+    /// Encode as a `dev_t`.
+    pub const fn to_dev(&self) -> u64 {
+        makedev(self.major, self.minor)
+    }
+}
+
+impl From<u64> for DeviceId {
+    fn from(dev: u64) -> Self {
+        DeviceId::from_dev(dev)
+    }
+}
+
+impl From<DeviceId> for u64 {
+    fn from(id: DeviceId) -> Self {
+        id.to_dev()
+    }
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.major, self.minor)
+    }
+}
+
+/// Error of parsing a `DeviceId` which is not `MAJ:MIN` in decimal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseDeviceIdError(());
+
+impl fmt::Display for ParseDeviceIdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid device number, expected `MAJ:MIN`")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseDeviceIdError {}
+
+impl FromStr for DeviceId {
+    type Err = ParseDeviceIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+        let mut next = || {
+            parts
+                .next()
+                .and_then(|part| part.parse().ok())
+                .ok_or(ParseDeviceIdError(()))
+        };
+        Ok(DeviceId::new(next()?, next()?))
+    }
+}
+
+impl statx {
+    /// ID of the device containing the file.
+    pub fn dev_id(&self) -> DeviceId {
+        DeviceId::new(self.stx_dev_major, self.stx_dev_minor)
+    }
+
+    /// Device ID of the file, if it is a block or character device.
+    pub fn rdev_id(&self) -> DeviceId {
+        DeviceId::new(self.stx_rdev_major, self.stx_rdev_minor)
+    }
+
+    /// ID of the device containing the file, as a `dev_t` like `st_dev`.
+    pub fn dev(&self) -> u64 {
+        self.dev_id().to_dev()
+    }
+
+    /// Device ID of the file, as a `dev_t` like `st_rdev`.
+    pub fn rdev(&self) -> u64 {
+        self.rdev_id().to_dev()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dev_layout() {
+        assert_eq!(makedev(8, 1), 0x801);
+        assert_eq!(makedev(0x12345, 0x6789a), 0x0001_2000_6783_459a);
+        for &(major, minor) in &[(0, 0), (8, 1), (259, 3), (!0, !0)] {
+            let dev = makedev(major, minor);
+            assert_eq!(DeviceId::from_dev(dev), DeviceId::new(major, minor));
+        }
+    }
+
+    #[test]
+    fn display_parse() {
+        extern crate std;
+        use std::string::ToString;
+
+        let id = DeviceId::new(259, 3);
+        assert_eq!(id.to_string(), "259:3");
+        assert_eq!("259:3".parse(), Ok(id));
+        assert!("259".parse::<DeviceId>().is_err());
+        assert!("259:3:1".parse::<DeviceId>().is_err());
+    }
+}
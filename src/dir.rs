@@ -0,0 +1,156 @@
+//! Directory handle for `statx()` relative to a `dirfd`.
+
+use crate::{statx, StatxError, StatxRequest, AT_FDCWD};
+use core::ffi::CStr;
+use libc::c_int;
+
+/// An `O_PATH | O_DIRECTORY` descriptor of a directory, closed on drop.
+///
+/// Lookups are relative to the directory itself rather than to its path, so
+/// they keep working on the same directory if it, or any of its parents, is
+/// renamed in the meantime.
+///
+/// Names must be a single component: empty names, `..` and names containing
+/// `/` are rejected with `InvalidArgument`, so no intermediate directory can
+/// be swapped for a symlink during the lookup. Walk down with `open_subdir`
+/// instead. A trailing symlink is only followed by `stat` and `stat_with`
+/// with `follow_symlinks`, and may point out of the directory.
+#[derive(Debug)]
+pub struct Dir {
+    fd: c_int,
+}
+
+impl Dir {
+    const OPEN_FLAGS: c_int = libc::O_PATH | libc::O_DIRECTORY | libc::O_CLOEXEC;
+
+    /// Open a directory, relative to the current working directory. Symlinks
+    /// are followed.
+    pub fn open(path: &CStr) -> Result<Dir, StatxError> {
+        Dir::open_at(AT_FDCWD, path, Dir::OPEN_FLAGS)
+    }
+
+    fn open_at(dirfd: c_int, path: &CStr, flags: c_int) -> Result<Dir, StatxError> {
+        let fd = unsafe { libc::openat(dirfd, path.as_ptr(), flags) };
+        if fd < 0 {
+            Err(StatxError::last())
+        } else {
+            Ok(Dir { fd })
+        }
+    }
+
+    /// Take ownership of a directory descriptor.
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be an open descriptor of a directory, which is not closed
+    /// elsewhere.
+    pub unsafe fn from_raw_fd(fd: c_int) -> Dir {
+        Dir { fd }
+    }
+
+    /// The underlying descriptor, still owned by the `Dir`.
+    pub fn as_raw_fd(&self) -> c_int {
+        self.fd
+    }
+
+    /// Open the subdirectory `name`, which must not be a symlink.
+    pub fn open_subdir(&self, name: &CStr) -> Result<Dir, StatxError> {
+        check_name(name)?;
+        Dir::open_at(self.fd, name, Dir::OPEN_FLAGS | libc::O_NOFOLLOW)
+    }
+
+    /// `statx()` of `name` with the default `StatxRequest`, following a
+    /// trailing symlink.
+    pub fn stat(&self, name: &CStr) -> Result<statx, StatxError> {
+        self.stat_with(name, &StatxRequest::new())
+    }
+
+    /// `statx()` of `name` with the default `StatxRequest`, about a trailing
+    /// symlink itself.
+    pub fn lstat(&self, name: &CStr) -> Result<statx, StatxError> {
+        self.stat_with(name, &StatxRequest::new().follow_symlinks(false))
+    }
+
+    /// `statx()` of `name` with a custom request.
+    pub fn stat_with(&self, name: &CStr, request: &StatxRequest) -> Result<statx, StatxError> {
+        check_name(name)?;
+        request.at(self.fd, name)
+    }
+
+    /// `statx()` of the directory itself.
+    pub fn stat_self(&self, request: &StatxRequest) -> Result<statx, StatxError> {
+        request.fd(self.fd)
+    }
+}
+
+/// Reject names which are not a single component of the directory.
+fn check_name(name: &CStr) -> Result<(), StatxError> {
+    match name.to_bytes() {
+        b"" | b".." => Err(StatxError::InvalidArgument),
+        name if name.contains(&b'/') => Err(StatxError::InvalidArgument),
+        _ => Ok(()),
+    }
+}
+
+impl Drop for Dir {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+#[cfg(feature = "std")]
+mod std_impls {
+    use super::Dir;
+    use core::mem;
+    use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, IntoRawFd, OwnedFd, RawFd};
+
+    impl AsRawFd for Dir {
+        fn as_raw_fd(&self) -> RawFd {
+            self.fd
+        }
+    }
+
+    impl AsFd for Dir {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            unsafe { BorrowedFd::borrow_raw(self.fd) }
+        }
+    }
+
+    impl IntoRawFd for Dir {
+        fn into_raw_fd(self) -> RawFd {
+            let fd = self.fd;
+            mem::forget(self);
+            fd
+        }
+    }
+
+    impl From<Dir> for OwnedFd {
+        fn from(dir: Dir) -> OwnedFd {
+            use std::os::unix::io::FromRawFd;
+
+            unsafe { OwnedFd::from_raw_fd(dir.into_raw_fd()) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(bytes: &[u8]) -> &CStr {
+        CStr::from_bytes_with_nul(bytes).unwrap()
+    }
+
+    #[test]
+    fn single_component() {
+        let dir = Dir::open(name(b".\0")).unwrap();
+        let invalid = Err(StatxError::InvalidArgument);
+        for &bad in &[&b"\0"[..], b"..\0", b"../x\0", b"sub/x\0", b"x/\0"] {
+            assert_eq!(dir.stat(name(bad)).map(|_| ()), invalid);
+            assert_eq!(dir.lstat(name(bad)).map(|_| ()), invalid);
+            assert_eq!(dir.open_subdir(name(bad)).map(|_| ()), invalid);
+        }
+        assert_eq!(check_name(name(b".\0")), Ok(()));
+        assert_eq!(check_name(name(b"..x\0")), Ok(()));
+    }
+}
@@ -0,0 +1,185 @@
+//! Error type of the safe wrappers.
+
+use core::fmt;
+use libc::c_int;
+
+/// Errors reported by `statx()`.
+///
+/// Every errno documented in statx(2) has its own variant, anything else is
+/// kept as-is in `Other`. `InteriorNul` is reported before calling the kernel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatxError {
+    /// `EACCES`: search permission is denied for a directory in the path.
+    PermissionDenied,
+    /// `EBADF`: `dirfd` is not a valid open file descriptor.
+    BadFileDescriptor,
+    /// `EFAULT`: `pathname` or `statxbuf` is outside the accessible address
+    /// space.
+    Fault,
+    /// `EINVAL`: invalid flag or reserved mask bit specified.
+    InvalidArgument,
+    /// `ELOOP`: too many symbolic links encountered while resolving the path.
+    TooManySymlinks,
+    /// `ENAMETOOLONG`: `pathname` is too long.
+    NameTooLong,
+    /// `ENOENT`: a component of the path does not exist, or the path is empty
+    /// without `AT_EMPTY_PATH`.
+    NotFound,
+    /// `ENOMEM`: out of kernel memory.
+    OutOfMemory,
+    /// `ENOTDIR`: a component of the path prefix is not a directory.
+    NotADirectory,
+    /// `ENOSYS`: the kernel does not implement `statx()` (before 4.11).
+    Unsupported,
+    /// The path contains a NUL byte, so it cannot be passed to the kernel.
+    /// Maps to `EINVAL`.
+    InteriorNul,
+    /// Any other errno.
+    Other(i32),
+}
+
+impl StatxError {
+    /// Map an errno value to the error.
+    pub fn from_errno(errno: c_int) -> Self {
+        match errno {
+            libc::EACCES => StatxError::PermissionDenied,
+            libc::EBADF => StatxError::BadFileDescriptor,
+            libc::EFAULT => StatxError::Fault,
+            libc::EINVAL => StatxError::InvalidArgument,
+            libc::ELOOP => StatxError::TooManySymlinks,
+            libc::ENAMETOOLONG => StatxError::NameTooLong,
+            libc::ENOENT => StatxError::NotFound,
+            libc::ENOMEM => StatxError::OutOfMemory,
+            libc::ENOTDIR => StatxError::NotADirectory,
+            libc::ENOSYS => StatxError::Unsupported,
+            errno => StatxError::Other(errno),
+        }
+    }
+
+    /// Get the errno value of the error.
+    ///
+    /// `from_errno` gives the error back, except for `InteriorNul`, which maps
+    /// to `EINVAL` and so comes back as `InvalidArgument`.
+    pub fn errno(&self) -> c_int {
+        match *self {
+            StatxError::PermissionDenied => libc::EACCES,
+            StatxError::BadFileDescriptor => libc::EBADF,
+            StatxError::Fault => libc::EFAULT,
+            StatxError::InvalidArgument => libc::EINVAL,
+            StatxError::TooManySymlinks => libc::ELOOP,
+            StatxError::NameTooLong => libc::ENAMETOOLONG,
+            StatxError::NotFound => libc::ENOENT,
+            StatxError::OutOfMemory => libc::ENOMEM,
+            StatxError::NotADirectory => libc::ENOTDIR,
+            StatxError::Unsupported => libc::ENOSYS,
+            StatxError::InteriorNul => libc::EINVAL,
+            StatxError::Other(errno) => errno,
+        }
+    }
+
+    /// Whether `statx()` itself is unavailable: `ENOSYS` before Linux 4.11, or
+    /// `EPERM` when blocked by a seccomp filter (e.g. older Docker profiles).
+    pub fn is_unavailable(&self) -> bool {
+        match *self {
+            StatxError::Unsupported => true,
+            StatxError::Other(errno) => errno == libc::EPERM,
+            _ => false,
+        }
+    }
+
+    /// Take the error of the last failed call from `errno`.
+    pub(crate) fn last() -> Self {
+        StatxError::from_errno(unsafe { *errno_location() })
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+unsafe fn errno_location() -> *mut c_int {
+    // glibc, musl and uClibc.
+    libc::__errno_location()
+}
+
+#[cfg(target_os = "android")]
+unsafe fn errno_location() -> *mut c_int {
+    // bionic.
+    libc::__errno()
+}
+
+impl fmt::Display for StatxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match *self {
+            StatxError::PermissionDenied => "permission denied",
+            StatxError::BadFileDescriptor => "bad file descriptor",
+            StatxError::Fault => "bad address",
+            StatxError::InvalidArgument => "invalid argument",
+            StatxError::TooManySymlinks => "too many levels of symbolic links",
+            StatxError::NameTooLong => "file name too long",
+            StatxError::NotFound => "no such file or directory",
+            StatxError::OutOfMemory => "out of memory",
+            StatxError::NotADirectory => "not a directory",
+            StatxError::Unsupported => "statx() is not supported by the kernel",
+            StatxError::InteriorNul => "path contains a NUL byte",
+            StatxError::Other(errno) => return write!(f, "os error {}", errno),
+        };
+        f.write_str(msg)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StatxError {}
+
+#[cfg(feature = "std")]
+impl From<StatxError> for std::io::Error {
+    /// An OS error of `errno`, except for `InteriorNul`, which the kernel never
+    /// reports, and keeps its message with `InvalidInput`.
+    fn from(err: StatxError) -> Self {
+        use std::io::{Error, ErrorKind};
+
+        match err {
+            StatxError::InteriorNul => {
+                Error::new(ErrorKind::InvalidInput, "path contains a NUL byte")
+            }
+            err => Error::from_raw_os_error(err.errno()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errno_round_trip() {
+        for &errno in &[libc::EACCES, libc::ENOENT, libc::ENOSYS, libc::EPERM] {
+            assert_eq!(StatxError::from_errno(errno).errno(), errno);
+        }
+        assert_eq!(
+            StatxError::from_errno(libc::ELOOP),
+            StatxError::TooManySymlinks
+        );
+        assert_eq!(
+            StatxError::from_errno(libc::EPERM),
+            StatxError::Other(libc::EPERM)
+        );
+
+        let nul = StatxError::InteriorNul;
+        assert_eq!(
+            StatxError::from_errno(nul.errno()),
+            StatxError::InvalidArgument
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn io_error() {
+        use std::io::{Error, ErrorKind};
+        use std::string::ToString;
+
+        let err = Error::from(StatxError::NotFound);
+        assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+        let err = Error::from(StatxError::InteriorNul);
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(err.raw_os_error(), None);
+        assert_eq!(err.to_string(), "path contains a NUL byte");
+    }
+}
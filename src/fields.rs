@@ -0,0 +1,302 @@
+//! Accessors of `statx` fields, checked against `stx_mask`.
+
+use crate::{statx, statx_timestamp, StatxAttributes, StatxMask};
+
+/// Mount ID of a file, from `stx_mnt_id`.
+///
+/// Both kinds match the mount IDs of `/proc/self/mountinfo` and `statmount()`
+/// for the same kind, but are not comparable to each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MountId {
+    /// `STATX_MNT_ID`: the ID may be reused after the mount is gone.
+    Reusable(u64),
+    /// `STATX_MNT_ID_UNIQUE`: the ID is never reused (since Linux 6.8).
+    Unique(u64),
+}
+
+impl MountId {
+    /// The raw ID.
+    pub fn get(&self) -> u64 {
+        match *self {
+            MountId::Reusable(id) | MountId::Unique(id) => id,
+        }
+    }
+}
+
+/// Alignment restrictions for direct I/O (`O_DIRECT`) on a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DirectIoAlignment {
+    /// Required alignment in bytes of user memory buffers.
+    pub mem_align: u32,
+    /// Required alignment in bytes of file offsets and I/O segment lengths.
+    pub offset_align: u32,
+    /// Required alignment in bytes of file offsets and I/O segment lengths
+    /// for reads, which may be smaller than `offset_align` (since Linux 6.14).
+    pub read_offset_align: u32,
+}
+
+/// Limits of atomic (untorn) writes on a file, with `RWF_ATOMIC`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AtomicWriteLimits {
+    /// Minimum size in bytes of an atomic write.
+    pub unit_min: u32,
+    /// Maximum size in bytes of an atomic write.
+    pub unit_max: u32,
+    /// Maximum number of segments (iovecs) of an atomic write.
+    pub segments_max: u32,
+}
+
+/// State of a file attribute, see `statx::attribute`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AttributeState {
+    /// The attribute is set on the file.
+    Set,
+    /// The attribute is not set on the file.
+    Clear,
+    /// The filesystem cannot report the attribute.
+    Unsupported,
+}
+
+impl statx {
+    /// What results were written, as `stx_mask`.
+    pub fn mask(&self) -> StatxMask {
+        StatxMask::from_bits_truncate(self.stx_mask)
+    }
+
+    /// Whether all fields of `mask` were filled in by the kernel.
+    pub fn has(&self, mask: StatxMask) -> bool {
+        self.mask().contains(mask)
+    }
+
+    fn filled<T>(&self, mask: StatxMask, value: T) -> Option<T> {
+        if self.has(mask) {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Preferred general I/O size. Always filled in.
+    pub fn blksize(&self) -> u32 {
+        self.stx_blksize
+    }
+
+    /// Flags conveying information about the file. Always filled in, but only
+    /// meaningful for the bits in `attributes_mask()`.
+    pub fn attributes(&self) -> StatxAttributes {
+        StatxAttributes::from_bits(self.stx_attributes)
+    }
+
+    /// Which bits of `attributes()` are supported by the filesystem.
+    pub fn attributes_mask(&self) -> StatxAttributes {
+        StatxAttributes::from_bits(self.stx_attributes_mask)
+    }
+
+    /// Query the state of `attr`, using `stx_attributes_mask` to tell apart
+    /// unset attributes from unsupported ones.
+    ///
+    /// With several attributes, the state is `Set` only if all of them are
+    /// set, and `Unsupported` if any of them is unsupported.
+    pub fn attribute(&self, attr: StatxAttributes) -> AttributeState {
+        if !self.attributes_mask().contains(attr) {
+            AttributeState::Unsupported
+        } else if self.attributes().contains(attr) {
+            AttributeState::Set
+        } else {
+            AttributeState::Clear
+        }
+    }
+
+    /// Number of hard links, if `STATX_NLINK` is set.
+    pub fn nlink(&self) -> Option<u32> {
+        self.filled(StatxMask::NLINK, self.stx_nlink)
+    }
+
+    /// User ID of owner, if `STATX_UID` is set.
+    pub fn uid(&self) -> Option<u32> {
+        self.filled(StatxMask::UID, self.stx_uid)
+    }
+
+    /// Group ID of owner, if `STATX_GID` is set.
+    pub fn gid(&self) -> Option<u32> {
+        self.filled(StatxMask::GID, self.stx_gid)
+    }
+
+    /// File mode, if `STATX_MODE` is set.
+    ///
+    /// The file type bits (`S_IFMT`) are only meaningful if `STATX_TYPE` is
+    /// set as well.
+    pub fn mode(&self) -> Option<u16> {
+        self.filled(StatxMask::MODE, self.stx_mode)
+    }
+
+    /// Inode number, if `STATX_INO` is set.
+    pub fn ino(&self) -> Option<u64> {
+        self.filled(StatxMask::INO, self.stx_ino)
+    }
+
+    /// File size, if `STATX_SIZE` is set.
+    pub fn size(&self) -> Option<u64> {
+        self.filled(StatxMask::SIZE, self.stx_size)
+    }
+
+    /// Number of 512-byte blocks allocated, if `STATX_BLOCKS` is set.
+    pub fn blocks(&self) -> Option<u64> {
+        self.filled(StatxMask::BLOCKS, self.stx_blocks)
+    }
+
+    /// Last access time, if `STATX_ATIME` is set.
+    pub fn atime(&self) -> Option<statx_timestamp> {
+        self.filled(StatxMask::ATIME, self.stx_atime)
+    }
+
+    /// File creation time, if `STATX_BTIME` is set.
+    pub fn btime(&self) -> Option<statx_timestamp> {
+        self.filled(StatxMask::BTIME, self.stx_btime)
+    }
+
+    /// Last attribute change time, if `STATX_CTIME` is set.
+    pub fn ctime(&self) -> Option<statx_timestamp> {
+        self.filled(StatxMask::CTIME, self.stx_ctime)
+    }
+
+    /// Last data modification time, if `STATX_MTIME` is set.
+    pub fn mtime(&self) -> Option<statx_timestamp> {
+        self.filled(StatxMask::MTIME, self.stx_mtime)
+    }
+
+    /// Mount ID, if `STATX_MNT_ID_UNIQUE` or `STATX_MNT_ID` is set.
+    ///
+    /// Bind mounts of the same device have the same `stx_dev_*` but different
+    /// mount IDs.
+    pub fn mnt_id(&self) -> Option<MountId> {
+        if self.has(StatxMask::MNT_ID_UNIQUE) {
+            Some(MountId::Unique(self.stx_mnt_id))
+        } else if self.has(StatxMask::MNT_ID) {
+            Some(MountId::Reusable(self.stx_mnt_id))
+        } else {
+            None
+        }
+    }
+
+    /// Subvolume identifier, if `STATX_SUBVOL` is set.
+    ///
+    /// Files on the same device (`stx_dev_*`) but in different subvolumes or
+    /// snapshots (btrfs, bcachefs) have different identifiers.
+    pub fn subvol(&self) -> Option<u64> {
+        self.filled(StatxMask::SUBVOL, self.stx_subvol)
+    }
+
+    /// Direct I/O alignment, if `STATX_DIOALIGN` is set and the file supports
+    /// direct I/O.
+    ///
+    /// The read alignment falls back to `offset_align` unless
+    /// `STATX_DIO_READ_ALIGN` is set and reports a value.
+    pub fn dio_alignment(&self) -> Option<DirectIoAlignment> {
+        if !self.has(StatxMask::DIOALIGN) || self.stx_dio_offset_align == 0 {
+            return None;
+        }
+        let read_offset_align = match self.stx_dio_read_offset_align {
+            align if align != 0 && self.has(StatxMask::DIO_READ_ALIGN) => align,
+            _ => self.stx_dio_offset_align,
+        };
+        Some(DirectIoAlignment {
+            mem_align: self.stx_dio_mem_align,
+            offset_align: self.stx_dio_offset_align,
+            read_offset_align,
+        })
+    }
+
+    /// Atomic write limits, if `STATX_WRITE_ATOMIC` is set and the file
+    /// supports atomic writes (`STATX_ATTR_WRITE_ATOMIC`).
+    pub fn atomic_write_limits(&self) -> Option<AtomicWriteLimits> {
+        if !self.has(StatxMask::WRITE_ATOMIC)
+            || !self.attributes().contains(StatxAttributes::WRITE_ATOMIC)
+        {
+            return None;
+        }
+        Some(AtomicWriteLimits {
+            unit_min: self.stx_atomic_write_unit_min,
+            unit_max: self.stx_atomic_write_unit_max,
+            segments_max: self.stx_atomic_write_segments_max,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{STATX_ATTR_APPEND, STATX_ATTR_IMMUTABLE, STATX_ATTR_WRITE_ATOMIC};
+    use crate::{STATX_DIOALIGN, STATX_DIO_READ_ALIGN};
+    use crate::{STATX_MNT_ID, STATX_WRITE_ATOMIC};
+    use core::mem::zeroed;
+
+    #[test]
+    fn mask_checked() {
+        let mut buf = unsafe { zeroed::<statx>() };
+        buf.stx_mask = (StatxMask::UID | StatxMask::SIZE).bits();
+        buf.stx_uid = 1000;
+        buf.stx_gid = 1000;
+        buf.stx_size = 42;
+        buf.stx_btime.tv_sec = 1;
+
+        assert_eq!(buf.uid(), Some(1000));
+        assert_eq!(buf.gid(), None);
+        assert_eq!(buf.size(), Some(42));
+        assert!(buf.btime().is_none());
+        assert!(buf.has(StatxMask::UID));
+        assert!(!buf.has(StatxMask::UID | StatxMask::GID));
+        assert_eq!(buf.mnt_id(), None);
+
+        buf.stx_mask |= STATX_MNT_ID;
+        buf.stx_mnt_id = 7;
+        assert_eq!(buf.mnt_id(), Some(MountId::Reusable(7)));
+    }
+
+    #[test]
+    fn attribute_state() {
+        let mut buf = unsafe { zeroed::<statx>() };
+        buf.stx_attributes_mask = STATX_ATTR_IMMUTABLE | STATX_ATTR_APPEND;
+        buf.stx_attributes = STATX_ATTR_APPEND;
+
+        let state = |attr| buf.attribute(attr);
+        assert_eq!(state(StatxAttributes::APPEND), AttributeState::Set);
+        assert_eq!(state(StatxAttributes::IMMUTABLE), AttributeState::Clear);
+        assert_eq!(state(StatxAttributes::DAX), AttributeState::Unsupported);
+    }
+
+    #[test]
+    fn dio_alignment() {
+        let mut buf = unsafe { zeroed::<statx>() };
+        buf.stx_dio_mem_align = 4;
+        buf.stx_dio_offset_align = 512;
+        buf.stx_dio_read_offset_align = 4;
+        assert_eq!(buf.dio_alignment(), None);
+
+        buf.stx_mask = STATX_DIOALIGN;
+        let align = buf.dio_alignment().unwrap();
+        assert_eq!(align.offset_align, 512);
+        assert_eq!(align.read_offset_align, 512);
+
+        buf.stx_mask |= STATX_DIO_READ_ALIGN;
+        assert_eq!(buf.dio_alignment().unwrap().read_offset_align, 4);
+
+        // Direct I/O not supported.
+        buf.stx_dio_offset_align = 0;
+        assert_eq!(buf.dio_alignment(), None);
+    }
+
+    #[test]
+    fn atomic_write_limits() {
+        let mut buf = unsafe { zeroed::<statx>() };
+        buf.stx_mask = STATX_WRITE_ATOMIC;
+        buf.stx_atomic_write_unit_min = 512;
+        buf.stx_atomic_write_unit_max = 4096;
+        buf.stx_atomic_write_segments_max = 1;
+        assert_eq!(buf.atomic_write_limits(), None);
+
+        buf.stx_attributes = STATX_ATTR_WRITE_ATOMIC;
+        let limits = buf.atomic_write_limits().unwrap();
+        assert_eq!((limits.unit_min, limits.unit_max), (512, 4096));
+    }
+}
@@ -0,0 +1,401 @@
+//! Typed bit sets for `stx_mask` and `stx_attributes`.
+
+use crate::{
+    STATX_ALL, STATX_ATIME, STATX_ATTR_APPEND, STATX_ATTR_AUTOMOUNT, STATX_ATTR_COMPRESSED,
+    STATX_ATTR_DAX, STATX_ATTR_ENCRYPTED, STATX_ATTR_IMMUTABLE, STATX_ATTR_MOUNT_ROOT,
+    STATX_ATTR_NODUMP, STATX_ATTR_VERITY, STATX_ATTR_WRITE_ATOMIC, STATX_BASIC_STATS, STATX_BLOCKS,
+    STATX_BTIME, STATX_CTIME, STATX_DIOALIGN, STATX_DIO_READ_ALIGN, STATX_GID, STATX_INO,
+    STATX_MNT_ID, STATX_MNT_ID_UNIQUE, STATX_MODE, STATX_MTIME, STATX_NLINK, STATX_SIZE,
+    STATX_SUBVOL, STATX_TYPE, STATX_UID, STATX_WRITE_ATOMIC, STATX__RESERVED,
+};
+use core::fmt;
+use core::ops::{
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Sub, SubAssign,
+};
+use libc::{__u64, c_uint};
+
+macro_rules! bit_set {
+    (
+        $(#[$attr:meta])*
+        pub struct $name:ident($ty:ty) {
+            valid = $valid:expr;
+            $($(#[$flag_attr:meta])* $flag:ident = $value:expr;)*
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name($ty);
+
+        impl $name {
+            $($(#[$flag_attr])* pub const $flag: $name = $name($value);)*
+
+            const NAMED: &'static [(&'static str, $ty)] = &[$((stringify!($flag), $value),)*];
+            #[cfg(feature = "serde")]
+            const VALID: $ty = $valid;
+
+            /// The empty set.
+            pub const fn empty() -> Self {
+                $name(0)
+            }
+
+            /// Remove the bits which are not valid for this set.
+            pub const fn from_bits_truncate(bits: $ty) -> Self {
+                $name(bits & $valid)
+            }
+
+            /// The raw bits.
+            pub const fn bits(&self) -> $ty {
+                self.0
+            }
+
+            /// Whether no bit is set.
+            pub const fn is_empty(&self) -> bool {
+                self.0 == 0
+            }
+
+            /// Whether all bits of `other` are set in `self`.
+            pub const fn contains(&self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Whether any bit of `other` is set in `self`.
+            pub const fn intersects(&self, other: Self) -> bool {
+                self.0 & other.0 != 0
+            }
+
+            /// Set the bits of `other`.
+            pub fn insert(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+
+            /// Clear the bits of `other`.
+            pub fn remove(&mut self, other: Self) {
+                self.0 &= !other.0;
+            }
+
+            /// Iterate over the set bits, one bit at a time, from the lowest.
+            pub fn iter(&self) -> impl Iterator<Item = Self> {
+                let bits = self.0;
+                (0..<$ty>::BITS)
+                    .map(|i| (1 as $ty) << i)
+                    .filter(move |bit| bits & bit != 0)
+                    .map($name)
+            }
+
+            /// The name of a single bit, e.g. `BTIME` for `STATX_BTIME`.
+            pub fn name(&self) -> Option<&'static str> {
+                $name::NAMED
+                    .iter()
+                    .find(|(_, value)| *value == self.0)
+                    .map(|(name, _)| *name)
+            }
+
+            /// The single bit named `name`, ignoring case, see `name`.
+            pub fn from_name(name: &str) -> Option<Self> {
+                $name::NAMED
+                    .iter()
+                    .find(|(known, _)| known.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| $name(*value))
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(concat!(stringify!($name), "("))?;
+                if self.is_empty() {
+                    f.write_str("empty")?;
+                }
+                for (i, bit) in self.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(" | ")?;
+                    }
+                    match bit.name() {
+                        Some(name) => f.write_str(name)?,
+                        None => write!(f, "{:#x}", bit.0)?,
+                    }
+                }
+                f.write_str(")")
+            }
+        }
+
+        /// A list of bit names, with unknown bits as integers, e.g.
+        /// `["TYPE", "BTIME", 2147483648]`.
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeSeq;
+
+                let mut seq = serializer.serialize_seq(Some(self.iter().count()))?;
+                for bit in self.iter() {
+                    match bit.name() {
+                        Some(name) => seq.serialize_element(name)?,
+                        None => seq.serialize_element(&u64::from(bit.0))?,
+                    }
+                }
+                seq.end()
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let visitor = de::BitsVisitor {
+                    from_name: |name| $name::from_name(name).map(|bit| u64::from(bit.0)),
+                    valid: u64::from($name::VALID),
+                };
+                // `valid` only has bits representable in `$ty`.
+                deserializer.deserialize_seq(visitor).map(|bits| $name(bits as $ty))
+            }
+        }
+
+        impl From<$name> for $ty {
+            fn from(set: $name) -> $ty {
+                set.0
+            }
+        }
+
+        impl BitOr for $name {
+            type Output = Self;
+            fn bitor(self, rhs: Self) -> Self {
+                $name(self.0 | rhs.0)
+            }
+        }
+
+        impl BitAnd for $name {
+            type Output = Self;
+            fn bitand(self, rhs: Self) -> Self {
+                $name(self.0 & rhs.0)
+            }
+        }
+
+        impl BitXor for $name {
+            type Output = Self;
+            fn bitxor(self, rhs: Self) -> Self {
+                $name(self.0 ^ rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self {
+                $name(self.0 & !rhs.0)
+            }
+        }
+
+        impl Not for $name {
+            type Output = Self;
+            fn not(self) -> Self {
+                $name(!self.0 & $valid)
+            }
+        }
+
+        impl BitOrAssign for $name {
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.0 |= rhs.0;
+            }
+        }
+
+        impl BitAndAssign for $name {
+            fn bitand_assign(&mut self, rhs: Self) {
+                self.0 &= rhs.0;
+            }
+        }
+
+        impl BitXorAssign for $name {
+            fn bitxor_assign(&mut self, rhs: Self) {
+                self.0 ^= rhs.0;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 &= !rhs.0;
+            }
+        }
+    };
+}
+
+#[cfg(feature = "serde")]
+mod de {
+    use core::convert::TryFrom;
+    use core::fmt;
+    use serde::de::{DeserializeSeed, Deserializer, Error, SeqAccess, Unexpected, Visitor};
+
+    /// Union of a list of bit names and integers.
+    pub(super) struct BitsVisitor {
+        pub(super) from_name: fn(&str) -> Option<u64>,
+        pub(super) valid: u64,
+    }
+
+    impl<'de> Visitor<'de> for BitsVisitor {
+        type Value = u64;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a list of bit names or integers")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<u64, A::Error> {
+            let mut bits = 0;
+            while let Some(bit) = seq.next_element_seed(&self)? {
+                bits |= bit;
+            }
+            Ok(bits)
+        }
+    }
+
+    /// One element of the list.
+    impl<'de> DeserializeSeed<'de> for &BitsVisitor {
+        type Value = u64;
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<u64, D::Error> {
+            deserializer.deserialize_any(self)
+        }
+    }
+
+    impl<'de> Visitor<'de> for &BitsVisitor {
+        type Value = u64;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a bit name or an integer")
+        }
+
+        fn visit_str<E: Error>(self, name: &str) -> Result<u64, E> {
+            (self.from_name)(name).ok_or_else(|| E::invalid_value(Unexpected::Str(name), &self))
+        }
+
+        fn visit_u64<E: Error>(self, bits: u64) -> Result<u64, E> {
+            if bits & !self.valid != 0 {
+                return Err(E::invalid_value(Unexpected::Unsigned(bits), &self));
+            }
+            Ok(bits)
+        }
+
+        fn visit_i64<E: Error>(self, bits: i64) -> Result<u64, E> {
+            match u64::try_from(bits) {
+                Ok(bits) => self.visit_u64(bits),
+                Err(_) => Err(E::invalid_value(Unexpected::Signed(bits), &self)),
+            }
+        }
+    }
+}
+
+bit_set! {
+    /// Set of `STATX_*` bits, as in the `mask` argument and `stx_mask`.
+    ///
+    /// `STATX__RESERVED` can never be part of the set, so a request built from
+    /// it is never rejected with `EINVAL` for that reason. Bits unknown to this
+    /// crate are kept as-is.
+    pub struct StatxMask(c_uint) {
+        valid = !STATX__RESERVED;
+        TYPE = STATX_TYPE;
+        MODE = STATX_MODE;
+        NLINK = STATX_NLINK;
+        UID = STATX_UID;
+        GID = STATX_GID;
+        ATIME = STATX_ATIME;
+        MTIME = STATX_MTIME;
+        CTIME = STATX_CTIME;
+        INO = STATX_INO;
+        SIZE = STATX_SIZE;
+        BLOCKS = STATX_BLOCKS;
+        BTIME = STATX_BTIME;
+        MNT_ID = STATX_MNT_ID;
+        DIOALIGN = STATX_DIOALIGN;
+        MNT_ID_UNIQUE = STATX_MNT_ID_UNIQUE;
+        SUBVOL = STATX_SUBVOL;
+        WRITE_ATOMIC = STATX_WRITE_ATOMIC;
+        DIO_READ_ALIGN = STATX_DIO_READ_ALIGN;
+    }
+}
+
+impl StatxMask {
+    /// `STATX_BASIC_STATS`: what `stat()` returns.
+    pub const BASIC_STATS: StatxMask = StatxMask(STATX_BASIC_STATS);
+    /// `STATX_ALL`: the basic stats and the birth time.
+    pub const ALL: StatxMask = StatxMask(STATX_ALL);
+
+    /// Convert raw bits, returning `None` if `STATX__RESERVED` is set.
+    pub const fn from_bits(bits: c_uint) -> Option<Self> {
+        if bits & STATX__RESERVED != 0 {
+            None
+        } else {
+            Some(StatxMask(bits))
+        }
+    }
+}
+
+bit_set! {
+    /// Set of `STATX_ATTR_*` bits, as in `stx_attributes` and
+    /// `stx_attributes_mask`.
+    pub struct StatxAttributes(__u64) {
+        valid = !0;
+        COMPRESSED = STATX_ATTR_COMPRESSED;
+        IMMUTABLE = STATX_ATTR_IMMUTABLE;
+        APPEND = STATX_ATTR_APPEND;
+        NODUMP = STATX_ATTR_NODUMP;
+        ENCRYPTED = STATX_ATTR_ENCRYPTED;
+        AUTOMOUNT = STATX_ATTR_AUTOMOUNT;
+        MOUNT_ROOT = STATX_ATTR_MOUNT_ROOT;
+        VERITY = STATX_ATTR_VERITY;
+        DAX = STATX_ATTR_DAX;
+        WRITE_ATOMIC = STATX_ATTR_WRITE_ATOMIC;
+    }
+}
+
+impl StatxAttributes {
+    /// Convert raw bits. Every bit is accepted.
+    pub const fn from_bits(bits: __u64) -> Self {
+        StatxAttributes(bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_ops() {
+        let mask = StatxMask::TYPE | StatxMask::MODE;
+        assert!(mask.contains(StatxMask::TYPE));
+        assert!(!mask.contains(StatxMask::TYPE | StatxMask::UID));
+        assert!(StatxMask::BASIC_STATS.contains(mask));
+        assert_eq!(mask - StatxMask::MODE, StatxMask::TYPE);
+        assert_eq!((StatxMask::ALL - StatxMask::BASIC_STATS), StatxMask::BTIME);
+        assert_eq!((!StatxMask::empty()).bits() & STATX__RESERVED, 0);
+        assert_eq!(mask.iter().count(), 2);
+
+        assert_eq!(StatxMask::from_bits(STATX__RESERVED | STATX_TYPE), None);
+        assert_eq!(
+            StatxMask::from_bits_truncate(STATX__RESERVED | STATX_TYPE),
+            StatxMask::TYPE
+        );
+    }
+
+    #[test]
+    fn debug_names() {
+        extern crate std;
+        use std::format;
+
+        assert_eq!(
+            format!("{:?}", StatxMask::TYPE | StatxMask::BTIME),
+            "StatxMask(TYPE | BTIME)"
+        );
+        assert_eq!(
+            format!("{:?}", StatxAttributes::from_bits(0x8000_0000_0000_0010)),
+            "StatxAttributes(IMMUTABLE | 0x8000000000000000)"
+        );
+        assert_eq!(format!("{:?}", StatxMask::empty()), "StatxMask(empty)");
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(StatxMask::MNT_ID.name(), Some("MNT_ID"));
+        assert_eq!(StatxMask::ALL.name(), None);
+        assert_eq!(StatxMask::from_name("btime"), Some(StatxMask::BTIME));
+        assert_eq!(
+            StatxAttributes::from_name("Immutable"),
+            Some(StatxAttributes::IMMUTABLE)
+        );
+        assert_eq!(StatxAttributes::from_name("BTIME"), None);
+    }
+}
@@ -0,0 +1,334 @@
+//! Futures resolving to the result of `statx()`, for async executors.
+//!
+//! `statx()` blocks, so it is run through a hook spawning blocking tasks, see
+//! `BlockingSpawner`, or with the `tokio` feature, `tokio::task::spawn_blocking`.
+//!
+//! With the `io-uring` feature, if the kernel supports `IORING_OP_STATX`,
+//! calls are instead submitted to a ring shared by the whole process, whose
+//! completions are reaped by a background thread. Operations of the ring are
+//! not subject to seccomp filters of the `statx` syscall, so requests with
+//! `StatxRequest::fallback` set always take the blocking path, where the
+//! filter applies and `fstatat()` is used if it blocks `statx()`.
+
+use crate::{statx, StatxError, StatxRequest};
+use std::boxed::Box;
+use std::future::Future;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// A hook to run blocking tasks outside of the executor threads.
+pub trait BlockingSpawner {
+    /// Run `task` on a thread where blocking is allowed.
+    ///
+    /// If the task is dropped without being run, e.g. on shutdown, the future
+    /// resolves to `ECANCELED`.
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send + 'static>);
+}
+
+impl<F> BlockingSpawner for F
+where
+    F: Fn(Box<dyn FnOnce() + Send + 'static>),
+{
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send + 'static>) {
+        self(task)
+    }
+}
+
+/// Run `request` on `path` without blocking the current thread.
+///
+/// The path is relative to the current working directory. See the module
+/// documentation about io_uring.
+pub fn statx_with<S, P>(spawner: &S, path: P, request: StatxRequest) -> StatxFuture
+where
+    S: BlockingSpawner + ?Sized,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref().as_os_str().as_bytes().to_vec();
+    let (future, completer) = StatxFuture::new();
+
+    #[cfg(feature = "io-uring")]
+    let (path, completer) = if request.has_fallback() {
+        (path, completer)
+    } else {
+        match uring::submit(&request, path, completer) {
+            Ok(()) => return future,
+            Err(not_submitted) => not_submitted,
+        }
+    };
+
+    spawner.spawn_blocking(Box::new(move || {
+        completer.complete(request.path_bytes(&path));
+    }));
+    future
+}
+
+/// Future of `statx_with`.
+#[derive(Debug)]
+pub struct StatxFuture {
+    shared: Arc<Mutex<State>>,
+}
+
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)] // Always behind an `Arc`.
+enum State {
+    Pending(Option<Waker>),
+    Done(Result<statx, StatxError>),
+    Taken,
+}
+
+impl StatxFuture {
+    fn new() -> (StatxFuture, Completer) {
+        let shared = Arc::new(Mutex::new(State::Pending(None)));
+        let completer = Completer(Some(shared.clone()));
+        (StatxFuture { shared }, completer)
+    }
+}
+
+impl Future for StatxFuture {
+    type Output = Result<statx, StatxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut state = self.shared.lock().unwrap_or_else(|err| err.into_inner());
+        match *state {
+            State::Pending(ref mut waker) => {
+                match waker {
+                    Some(waker) if waker.will_wake(cx.waker()) => {}
+                    _ => *waker = Some(cx.waker().clone()),
+                }
+                Poll::Pending
+            }
+            State::Done(_) => match std::mem::replace(&mut *state, State::Taken) {
+                State::Done(result) => Poll::Ready(result),
+                _ => unreachable!(),
+            },
+            State::Taken => panic!("`StatxFuture` polled after completion"),
+        }
+    }
+}
+
+/// Sending side of a `StatxFuture`, resolving it to `ECANCELED` if dropped.
+struct Completer(Option<Arc<Mutex<State>>>);
+
+impl Completer {
+    fn complete(mut self, result: Result<statx, StatxError>) {
+        self.set(result);
+    }
+
+    fn set(&mut self, result: Result<statx, StatxError>) {
+        if let Some(shared) = self.0.take() {
+            let mut state = shared.lock().unwrap_or_else(|err| err.into_inner());
+            let waker = match std::mem::replace(&mut *state, State::Done(result)) {
+                State::Pending(waker) => waker,
+                _ => None,
+            };
+            drop(state);
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl Drop for Completer {
+    fn drop(&mut self) {
+        self.set(Err(StatxError::Other(libc::ECANCELED)));
+    }
+}
+
+/// Run `request` on `path` with `tokio::task::spawn_blocking`, or io_uring
+/// if available.
+///
+/// Must be called from within a Tokio runtime.
+#[cfg(feature = "tokio")]
+pub async fn tokio_statx<P: AsRef<Path>>(
+    path: P,
+    request: StatxRequest,
+) -> Result<statx, StatxError> {
+    let spawner = |task: Box<dyn FnOnce() + Send + 'static>| {
+        tokio::task::spawn_blocking(task);
+    };
+    statx_with(&spawner, path, request).await
+}
+
+#[cfg(feature = "io-uring")]
+mod uring {
+    use super::Completer;
+    use crate::uring::{IoUring, StatxOp};
+    use crate::{StatxError, StatxRequest};
+    use io_uring::{opcode, types, Probe};
+    use libc::c_int;
+    use std::collections::VecDeque;
+    use std::string::ToString;
+    use std::sync::{Arc, Mutex, OnceLock};
+    use std::vec::Vec;
+    use std::{io, mem, thread};
+
+    const ENTRIES: u32 = 256;
+    /// Keep completions within the default completion queue size.
+    const MAX_IN_FLIGHT: usize = 2 * ENTRIES as usize - 1;
+    /// `user_data` of the read of the eventfd.
+    const WAKE: u64 = u64::MAX;
+
+    type Pending = (StatxOp, Completer);
+
+    #[derive(Default)]
+    struct Queue {
+        pending: Vec<Pending>,
+        /// The ring failed, requests must use the blocking path.
+        dead: bool,
+    }
+
+    struct Driver {
+        queue: Arc<Mutex<Queue>>,
+        /// Written to wake the driver thread up.
+        eventfd: c_int,
+    }
+
+    static DRIVER: OnceLock<Option<Driver>> = OnceLock::new();
+
+    /// Submit the request to the shared ring, or give the arguments back if
+    /// io_uring is not usable.
+    pub(super) fn submit(
+        request: &StatxRequest,
+        path: Vec<u8>,
+        completer: Completer,
+    ) -> Result<(), (Vec<u8>, Completer)> {
+        let driver = match DRIVER.get_or_init(start) {
+            Some(driver) => driver,
+            None => return Err((path, completer)),
+        };
+        let op = match StatxOp::path(request, &path) {
+            Ok(op) => op,
+            Err(err) => {
+                completer.complete(Err(err));
+                return Ok(());
+            }
+        };
+        {
+            let mut queue = lock(&driver.queue);
+            if queue.dead {
+                return Err((path, completer));
+            }
+            queue.pending.push((op, completer));
+        }
+        let one = 1u64;
+        unsafe { libc::write(driver.eventfd, &one as *const u64 as *const _, 8) };
+        Ok(())
+    }
+
+    fn lock(queue: &Mutex<Queue>) -> std::sync::MutexGuard<'_, Queue> {
+        queue.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn start() -> Option<Driver> {
+        let ring = IoUring::new(ENTRIES).ok()?;
+        let mut probe = Probe::new();
+        ring.submitter().register_probe(&mut probe).ok()?;
+        if !probe.is_supported(opcode::Statx::CODE) {
+            return None;
+        }
+
+        let eventfd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+        if eventfd < 0 {
+            return None;
+        }
+        let queue = Arc::new(Mutex::new(Queue::default()));
+        let driver_queue = queue.clone();
+        let spawned = thread::Builder::new()
+            .name("statx-uring".to_string())
+            .spawn(move || run(ring, eventfd, &driver_queue));
+        if spawned.is_err() {
+            unsafe { libc::close(eventfd) };
+            return None;
+        }
+        Some(Driver { queue, eventfd })
+    }
+
+    fn run(mut ring: IoUring, eventfd: c_int, queue: &Mutex<Queue>) {
+        // Operations in flight, indexed by `user_data`.
+        let mut slots: Vec<Option<Pending>> = Vec::new();
+        let mut free_slots = Vec::new();
+        let mut backlog = VecDeque::new();
+        let mut wake_buf = 0u64;
+        let mut wake_armed = false;
+
+        loop {
+            backlog.extend(lock(queue).pending.drain(..));
+
+            let in_flight = slots.len() - free_slots.len();
+            {
+                let mut sq = ring.submission();
+                if !wake_armed {
+                    let buf = &mut wake_buf as *mut u64 as *mut u8;
+                    let entry = opcode::Read::new(types::Fd(eventfd), buf, 8)
+                        .build()
+                        .user_data(WAKE);
+                    // `wake_buf` lives as long as the ring.
+                    wake_armed = unsafe { sq.push(&entry) }.is_ok();
+                }
+                for _ in in_flight..MAX_IN_FLIGHT {
+                    if sq.is_full() {
+                        break;
+                    }
+                    let (op, completer) = match backlog.pop_front() {
+                        Some(pending) => pending,
+                        None => break,
+                    };
+                    let slot = free_slots.pop().unwrap_or_else(|| {
+                        slots.push(None);
+                        slots.len() - 1
+                    });
+                    let entry = op.entry().user_data(slot as u64);
+                    // The operation is kept in `slots` until completed.
+                    unsafe { sq.push(&entry) }.expect("submission queue is full");
+                    slots[slot] = Some((op, completer));
+                }
+            }
+
+            if let Err(err) = ring.submit_and_wait(1) {
+                match err.raw_os_error() {
+                    Some(libc::EINTR) | Some(libc::EAGAIN) | Some(libc::EBUSY) => {}
+                    _ => return shut_down(err, slots, backlog, queue),
+                }
+            }
+
+            for cqe in ring.completion() {
+                if cqe.user_data() == WAKE {
+                    wake_armed = false;
+                    continue;
+                }
+                let slot = cqe.user_data() as usize;
+                if let Some((op, completer)) = slots[slot].take() {
+                    completer.complete(op.complete(cqe.result()));
+                    free_slots.push(slot);
+                }
+            }
+        }
+    }
+
+    /// Give up on the ring: operations in flight are leaked since the kernel
+    /// may still write to them, and all futures resolve to the error.
+    fn shut_down(
+        err: io::Error,
+        slots: Vec<Option<Pending>>,
+        backlog: VecDeque<Pending>,
+        queue: &Mutex<Queue>,
+    ) {
+        let err = StatxError::from_errno(err.raw_os_error().unwrap_or(libc::EIO));
+        let pending = {
+            let mut queue = lock(queue);
+            queue.dead = true;
+            mem::take(&mut queue.pending)
+        };
+        for (op, completer) in slots.into_iter().flatten() {
+            mem::forget(op);
+            completer.complete(Err(err));
+        }
+        for (_, completer) in backlog.into_iter().chain(pending) {
+            completer.complete(Err(err));
+        }
+    }
+}
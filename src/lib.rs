@@ -7,9 +7,50 @@
 #![no_std]
 #![deny(warnings)]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::ffi::CStr;
+use core::mem;
 use libc::syscall;
 use libc::{__s32, __u16, __u32, __u64, c_char, c_int, c_long, c_uint};
 
+#[cfg(feature = "std")]
+mod batch;
+mod dev;
+mod dir;
+mod error;
+mod fields;
+mod flags;
+#[cfg(feature = "async")]
+pub mod future;
+mod mode;
+mod path;
+mod probe;
+mod request;
+#[cfg(feature = "serde")]
+mod serialize;
+mod stat;
+mod time;
+#[cfg(feature = "io-uring")]
+pub mod uring;
+
+#[cfg(feature = "std")]
+pub use crate::batch::{statx_many, statx_many_into, statx_many_with_threads};
+pub use crate::dev::{major, makedev, minor, DeviceId, ParseDeviceIdError};
+pub use crate::dir::Dir;
+pub use crate::error::StatxError;
+pub use crate::fields::{AtomicWriteLimits, AttributeState, DirectIoAlignment, MountId};
+pub use crate::flags::{StatxAttributes, StatxMask};
+pub use crate::mode::{Access, FileMode, FileType, Permissions};
+pub use crate::path::{statx_bytes, with_c_path, PATH_MAX};
+pub use crate::probe::{kernel_support, KernelSupport, KernelVersion};
+pub use crate::request::{StatxRequest, SyncMode};
+pub use crate::time::{SignedDuration, TimestampError};
+
 /// Timestamp structure for the timestamps in struct statx.
 ///
 /// tv_sec holds the number of seconds before (negative) or after (positive)
@@ -108,16 +149,109 @@ pub struct statx {
     pub stx_dev_minor: __u32,
 
     // 0x90
+    /// ID of the mount containing the file [if STATX_MNT_ID or
+    /// STATX_MNT_ID_UNIQUE]
+    pub stx_mnt_id: __u64,
+    /// Memory buffer alignment for direct I/O [if STATX_DIOALIGN]
+    pub stx_dio_mem_align: __u32,
+    /// File offset alignment for direct I/O [if STATX_DIOALIGN]
+    pub stx_dio_offset_align: __u32,
+
+    // 0xa0
+    /// Subvolume identifier [if STATX_SUBVOL]
+    pub stx_subvol: __u64,
+    /// Minimum size of an atomic write in bytes [if STATX_WRITE_ATOMIC]
+    pub stx_atomic_write_unit_min: __u32,
+    /// Maximum size of an atomic write in bytes [if STATX_WRITE_ATOMIC]
+    pub stx_atomic_write_unit_max: __u32,
+
+    // 0xb0
+    /// Maximum number of segments (iovecs) of an atomic write
+    /// [if STATX_WRITE_ATOMIC]
+    pub stx_atomic_write_segments_max: __u32,
+    /// File offset alignment for direct I/O reads [if STATX_DIO_READ_ALIGN]
+    pub stx_dio_read_offset_align: __u32,
+
+    // 0xb8
     /// Spare space for future expansion
-    pub __spare2: [__u64; 14],
+    pub __spare2: [__u64; 9],
     // 0x100
 }
 
+// Syscall numbers, see `arch/*/include/uapi/asm/unistd*.h` and
+// `include/uapi/asm-generic/unistd.h` in the kernel tree.
+
+#[cfg(all(target_arch = "x86_64", target_pointer_width = "64"))]
 #[allow(non_upper_case_globals)]
 pub const SYS_statx: c_long = 332;
+/// x32 ABI: `__X32_SYSCALL_BIT | 332`.
+#[cfg(all(target_arch = "x86_64", target_pointer_width = "32"))]
+#[allow(non_upper_case_globals)]
+pub const SYS_statx: c_long = 0x4000_0000 + 332;
+#[cfg(target_arch = "x86")]
+#[allow(non_upper_case_globals)]
+pub const SYS_statx: c_long = 383;
+#[cfg(target_arch = "arm")]
+#[allow(non_upper_case_globals)]
+pub const SYS_statx: c_long = 397;
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv32",
+    target_arch = "riscv64",
+    target_arch = "loongarch64"
+))]
+#[allow(non_upper_case_globals)]
+pub const SYS_statx: c_long = 291;
+#[cfg(any(target_arch = "powerpc", target_arch = "powerpc64"))]
+#[allow(non_upper_case_globals)]
+pub const SYS_statx: c_long = 383;
+#[cfg(target_arch = "s390x")]
+#[allow(non_upper_case_globals)]
+pub const SYS_statx: c_long = 379;
+#[cfg(any(target_arch = "sparc", target_arch = "sparc64"))]
+#[allow(non_upper_case_globals)]
+pub const SYS_statx: c_long = 360;
+/// o32 ABI: `__NR_Linux (4000) + 366`.
+#[cfg(target_arch = "mips")]
+#[allow(non_upper_case_globals)]
+pub const SYS_statx: c_long = 4000 + 366;
+/// n64 ABI: `__NR_Linux (5000) + 326`.
+#[cfg(all(target_arch = "mips64", target_pointer_width = "64"))]
+#[allow(non_upper_case_globals)]
+pub const SYS_statx: c_long = 5000 + 326;
+/// n32 ABI: `__NR_Linux (6000) + 330`.
+#[cfg(all(target_arch = "mips64", target_pointer_width = "32"))]
+#[allow(non_upper_case_globals)]
+pub const SYS_statx: c_long = 6000 + 330;
+
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "x86",
+    target_arch = "arm",
+    target_arch = "aarch64",
+    target_arch = "riscv32",
+    target_arch = "riscv64",
+    target_arch = "loongarch64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64",
+    target_arch = "s390x",
+    target_arch = "sparc",
+    target_arch = "sparc64",
+    target_arch = "mips",
+    target_arch = "mips64"
+)))]
+compile_error!("`SYS_statx` is not known for this target architecture");
 
 // Flags
 
+/// Special value of `dirfd` for the current working directory.
+pub const AT_FDCWD: c_int = -100;
+
+pub const AT_SYMLINK_NOFOLLOW: c_uint = 0x0000_0100;
+pub const AT_NO_AUTOMOUNT: c_uint = 0x0000_0800;
+pub const AT_EMPTY_PATH: c_uint = 0x0000_1000;
+
+pub const AT_STATX_SYNC_TYPE: c_uint = 0x0000_6000;
 pub const AT_STATX_SYNC_AS_STAT: c_uint = 0x0000_0000;
 pub const AT_STATX_FORCE_SYNC: c_uint = 0x0000_2000;
 pub const AT_STATX_DONT_SYNC: c_uint = 0x0000_4000;
@@ -136,6 +270,18 @@ pub const STATX_BLOCKS: c_uint = 0x0000_0400;
 pub const STATX_BASIC_STATS: c_uint = 0x0000_07ff;
 pub const STATX_BTIME: c_uint = 0x0000_0800;
 pub const STATX_ALL: c_uint = 0x0000_0fff;
+/// Want stx_mnt_id (since Linux 5.8).
+pub const STATX_MNT_ID: c_uint = 0x0000_1000;
+/// Want stx_dio_mem_align and stx_dio_offset_align (since Linux 6.1).
+pub const STATX_DIOALIGN: c_uint = 0x0000_2000;
+/// Want the unique, never reused stx_mnt_id (since Linux 6.8).
+pub const STATX_MNT_ID_UNIQUE: c_uint = 0x0000_4000;
+/// Want stx_subvol (since Linux 6.10).
+pub const STATX_SUBVOL: c_uint = 0x0000_8000;
+/// Want stx_atomic_write_* (since Linux 6.11).
+pub const STATX_WRITE_ATOMIC: c_uint = 0x0001_0000;
+/// Want stx_dio_read_offset_align (since Linux 6.14).
+pub const STATX_DIO_READ_ALIGN: c_uint = 0x0002_0000;
 pub const STATX__RESERVED: c_uint = 0x8000_0000;
 
 // File attributes.
@@ -147,11 +293,24 @@ pub const STATX_ATTR_NODUMP: __u64 = 0x0000_0040;
 pub const STATX_ATTR_ENCRYPTED: __u64 = 0x0000_0800;
 
 pub const STATX_ATTR_AUTOMOUNT: __u64 = 0x0000_1000;
+/// Root of a mount (since Linux 5.8).
+pub const STATX_ATTR_MOUNT_ROOT: __u64 = 0x0000_2000;
+/// Verity protected file (since Linux 5.5).
+pub const STATX_ATTR_VERITY: __u64 = 0x0010_0000;
+/// File is currently in DAX state (since Linux 5.8).
+pub const STATX_ATTR_DAX: __u64 = 0x0020_0000;
+/// The file supports torn-write protection (since Linux 6.11).
+pub const STATX_ATTR_WRITE_ATOMIC: __u64 = 0x0040_0000;
 
 /// statx - get file status (extended)
 ///
 /// See also:
 /// http://man7.org/linux/man-pages/man2/statx.2.html
+///
+/// # Safety
+///
+/// `pathname` must point to a NUL-terminated string and `statxbuf` must be
+/// valid for writes of a `statx`, as required by the syscall.
 pub unsafe fn statx(
     dirfd: c_int,
     pathname: *const c_char,
@@ -162,6 +321,49 @@ pub unsafe fn statx(
     syscall(SYS_statx, dirfd, pathname, flags, mask, statxbuf) as c_int
 }
 
+/// Safe wrapper of `statx()` for a path relative to the current working
+/// directory.
+///
+/// On failure, `errno` is mapped into a `StatxError`.
+pub fn statx_path(path: &CStr, flags: c_int, mask: c_uint) -> Result<statx, StatxError> {
+    statx_at(AT_FDCWD, path, flags, mask)
+}
+
+/// Safe wrapper of `statx()` for an open file, using `AT_EMPTY_PATH`.
+#[cfg(feature = "std")]
+pub fn statx_fd<Fd: std::os::unix::io::AsFd>(fd: Fd, mask: StatxMask) -> Result<statx, StatxError> {
+    use std::os::unix::io::AsRawFd;
+
+    StatxRequest::new().mask(mask).fd(fd.as_fd().as_raw_fd())
+}
+
+pub(crate) fn statx_at(
+    dirfd: c_int,
+    path: &CStr,
+    flags: c_int,
+    mask: c_uint,
+) -> Result<statx, StatxError> {
+    let mut buf = unsafe { mem::zeroed::<statx>() };
+    statx_into(dirfd, path, flags, mask, &mut buf).map(|()| buf)
+}
+
+/// Like `statx_at`, but into an existing buffer, which the kernel overwrites
+/// entirely on success.
+pub(crate) fn statx_into(
+    dirfd: c_int,
+    path: &CStr,
+    flags: c_int,
+    mask: c_uint,
+    buf: &mut statx,
+) -> Result<(), StatxError> {
+    let ret = unsafe { statx(dirfd, path.as_ptr(), flags, mask, buf) };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(StatxError::last())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,6 +381,32 @@ mod tests {
         assert_eq!(offset_of!(statx, stx_ino), 0x20);
         assert_eq!(offset_of!(statx, stx_atime), 0x40);
         assert_eq!(offset_of!(statx, stx_rdev_major), 0x80);
-        assert_eq!(offset_of!(statx, __spare2), 0x90);
+        assert_eq!(offset_of!(statx, stx_mnt_id), 0x90);
+        assert_eq!(offset_of!(statx, stx_dio_mem_align), 0x98);
+        assert_eq!(offset_of!(statx, stx_dio_offset_align), 0x9c);
+        assert_eq!(offset_of!(statx, stx_subvol), 0xa0);
+        assert_eq!(offset_of!(statx, stx_atomic_write_unit_min), 0xa8);
+        assert_eq!(offset_of!(statx, stx_atomic_write_unit_max), 0xac);
+        assert_eq!(offset_of!(statx, stx_atomic_write_segments_max), 0xb0);
+        assert_eq!(offset_of!(statx, stx_dio_read_offset_align), 0xb4);
+        assert_eq!(offset_of!(statx, __spare2), 0xb8);
+    }
+
+    #[test]
+    #[cfg(any(target_env = "gnu", target_env = "musl"))]
+    fn check_syscall_number() {
+        assert_eq!(SYS_statx, libc::SYS_statx);
+    }
+
+    #[test]
+    fn check_at_flags() {
+        assert_eq!(AT_FDCWD, libc::AT_FDCWD);
+        assert_eq!(AT_SYMLINK_NOFOLLOW as c_int, libc::AT_SYMLINK_NOFOLLOW);
+        assert_eq!(AT_NO_AUTOMOUNT as c_int, libc::AT_NO_AUTOMOUNT);
+        assert_eq!(AT_EMPTY_PATH as c_int, libc::AT_EMPTY_PATH);
+        assert_eq!(
+            AT_STATX_SYNC_TYPE,
+            AT_STATX_SYNC_AS_STAT | AT_STATX_FORCE_SYNC | AT_STATX_DONT_SYNC
+        );
     }
 }
@@ -0,0 +1,223 @@
+//! File type and permission bits of `stx_mode`.
+
+use crate::{statx, StatxMask};
+use core::fmt;
+use libc::mode_t;
+
+/// File type, from the `S_IFMT` bits of `stx_mode`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FileType {
+    Regular,
+    Directory,
+    Symlink,
+    BlockDevice,
+    CharDevice,
+    Fifo,
+    Socket,
+    /// A value of `S_IFMT` unknown to this crate.
+    Unknown,
+}
+
+impl FileType {
+    /// Decode the `S_IFMT` bits of a mode.
+    pub fn from_mode(mode: u16) -> Self {
+        match mode_t::from(mode) & libc::S_IFMT {
+            libc::S_IFREG => FileType::Regular,
+            libc::S_IFDIR => FileType::Directory,
+            libc::S_IFLNK => FileType::Symlink,
+            libc::S_IFBLK => FileType::BlockDevice,
+            libc::S_IFCHR => FileType::CharDevice,
+            libc::S_IFIFO => FileType::Fifo,
+            libc::S_IFSOCK => FileType::Socket,
+            _ => FileType::Unknown,
+        }
+    }
+
+    /// The character used by `ls -l`, `?` if unknown.
+    pub fn as_char(&self) -> char {
+        match *self {
+            FileType::Regular => '-',
+            FileType::Directory => 'd',
+            FileType::Symlink => 'l',
+            FileType::BlockDevice => 'b',
+            FileType::CharDevice => 'c',
+            FileType::Fifo => 'p',
+            FileType::Socket => 's',
+            FileType::Unknown => '?',
+        }
+    }
+}
+
+/// Read, write and execute permissions of one class of users.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Access {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl Access {
+    fn from_bits(bits: u16) -> Self {
+        Access {
+            read: bits & 0o4 != 0,
+            write: bits & 0o2 != 0,
+            execute: bits & 0o1 != 0,
+        }
+    }
+}
+
+/// Permission bits of `stx_mode`, including setuid, setgid and sticky.
+///
+/// Displayed like `ls -l` without the file type, e.g. `rwxr-sr-x`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Permissions(u16);
+
+impl Permissions {
+    /// Take the permission bits of a mode, ignoring the file type bits.
+    pub const fn from_mode(mode: u16) -> Self {
+        Permissions(mode & 0o7777)
+    }
+
+    /// The permission bits, as in `chmod`.
+    pub const fn bits(&self) -> u16 {
+        self.0
+    }
+
+    /// Whether `S_ISUID` is set.
+    pub const fn is_setuid(&self) -> bool {
+        self.0 & 0o4000 != 0
+    }
+
+    /// Whether `S_ISGID` is set.
+    pub const fn is_setgid(&self) -> bool {
+        self.0 & 0o2000 != 0
+    }
+
+    /// Whether `S_ISVTX` is set.
+    pub const fn is_sticky(&self) -> bool {
+        self.0 & 0o1000 != 0
+    }
+
+    /// Permissions of the owner.
+    pub fn user(&self) -> Access {
+        Access::from_bits(self.0 >> 6)
+    }
+
+    /// Permissions of the group.
+    pub fn group(&self) -> Access {
+        Access::from_bits(self.0 >> 3)
+    }
+
+    /// Permissions of other users.
+    pub fn other(&self) -> Access {
+        Access::from_bits(self.0)
+    }
+}
+
+impl fmt::Display for Permissions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use core::fmt::Write;
+
+        let classes = [
+            (self.user(), self.is_setuid(), 's'),
+            (self.group(), self.is_setgid(), 's'),
+            (self.other(), self.is_sticky(), 't'),
+        ];
+        for &(access, special, special_char) in &classes {
+            f.write_char(if access.read { 'r' } else { '-' })?;
+            f.write_char(if access.write { 'w' } else { '-' })?;
+            f.write_char(match (access.execute, special) {
+                (true, false) => 'x',
+                (false, false) => '-',
+                (true, true) => special_char,
+                (false, true) => special_char.to_ascii_uppercase(),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// File type and permissions, displayed like `ls -l`, e.g. `drwxr-sr-x`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FileMode {
+    pub file_type: FileType,
+    pub permissions: Permissions,
+}
+
+impl FileMode {
+    /// Decode a whole mode.
+    pub fn from_mode(mode: u16) -> Self {
+        FileMode {
+            file_type: FileType::from_mode(mode),
+            permissions: Permissions::from_mode(mode),
+        }
+    }
+}
+
+impl fmt::Display for FileMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use core::fmt::Write;
+
+        f.write_char(self.file_type.as_char())?;
+        fmt::Display::fmt(&self.permissions, f)
+    }
+}
+
+impl statx {
+    /// File type, if `STATX_TYPE` is set.
+    pub fn file_type(&self) -> Option<FileType> {
+        if self.has(StatxMask::TYPE) {
+            Some(FileType::from_mode(self.stx_mode))
+        } else {
+            None
+        }
+    }
+
+    /// Permission bits, if `STATX_MODE` is set.
+    pub fn permissions(&self) -> Option<Permissions> {
+        self.mode().map(Permissions::from_mode)
+    }
+
+    /// File type and permissions, if both `STATX_TYPE` and `STATX_MODE` are
+    /// set.
+    pub fn file_mode(&self) -> Option<FileMode> {
+        if self.has(StatxMask::TYPE | StatxMask::MODE) {
+            Some(FileMode::from_mode(self.stx_mode))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::zeroed;
+
+    #[test]
+    fn render_mode() {
+        extern crate std;
+        use std::string::ToString;
+
+        let render = |mode| FileMode::from_mode(mode).to_string();
+        assert_eq!(render(0o042755), "drwxr-sr-x");
+        assert_eq!(render(0o041777), "drwxrwxrwt");
+        assert_eq!(render(0o104644), "-rwSr--r--");
+        assert_eq!(render(0o120777), "lrwxrwxrwx");
+        assert_eq!(render(0o020620), "crw--w----");
+    }
+
+    #[test]
+    fn mask_checked() {
+        let mut buf = unsafe { zeroed::<statx>() };
+        buf.stx_mode = 0o140755;
+        buf.stx_mask = crate::STATX_TYPE;
+        assert_eq!(buf.file_type(), Some(FileType::Socket));
+        assert_eq!(buf.permissions(), None);
+        assert_eq!(buf.file_mode(), None);
+
+        buf.stx_mask |= crate::STATX_MODE;
+        assert_eq!(buf.permissions().map(|p| p.bits()), Some(0o755));
+        assert!(buf.file_mode().is_some());
+    }
+}
@@ -0,0 +1,104 @@
+//! Conversion of byte paths to NUL-terminated C strings without allocation.
+
+use crate::{statx, StatxError, StatxRequest};
+use core::ffi::CStr;
+use core::mem::MaybeUninit;
+use core::{ptr, slice};
+use libc::{c_int, c_uint};
+
+/// Maximum length of a path accepted by the kernel, including the NUL.
+pub const PATH_MAX: usize = libc::PATH_MAX as usize;
+
+/// Call `f` with `path` as a NUL-terminated C string.
+///
+/// Paths shorter than `PATH_MAX` are copied into a buffer on the stack. Longer
+/// ones are copied to the heap with the `alloc` feature, and fail with
+/// `StatxError::NameTooLong` otherwise, as the kernel would do anyway.
+///
+/// Paths containing a NUL byte fail with `StatxError::InteriorNul`, instead of
+/// being silently truncated.
+pub fn with_c_path<T, F>(path: &[u8], f: F) -> Result<T, StatxError>
+where
+    F: FnOnce(&CStr) -> Result<T, StatxError>,
+{
+    if path.contains(&0) {
+        return Err(StatxError::InteriorNul);
+    }
+    if path.len() < PATH_MAX {
+        let mut buf = [MaybeUninit::<u8>::uninit(); PATH_MAX];
+        let c_path = unsafe {
+            let p = buf.as_mut_ptr() as *mut u8;
+            ptr::copy_nonoverlapping(path.as_ptr(), p, path.len());
+            p.add(path.len()).write(0);
+            CStr::from_bytes_with_nul_unchecked(slice::from_raw_parts(p, path.len() + 1))
+        };
+        return f(c_path);
+    }
+    with_heap_c_path(path, f)
+}
+
+#[cfg(feature = "alloc")]
+fn with_heap_c_path<T, F>(path: &[u8], f: F) -> Result<T, StatxError>
+where
+    F: FnOnce(&CStr) -> Result<T, StatxError>,
+{
+    let mut buf = alloc::vec::Vec::with_capacity(path.len() + 1);
+    buf.extend_from_slice(path);
+    buf.push(0);
+    f(unsafe { CStr::from_bytes_with_nul_unchecked(&buf) })
+}
+
+#[cfg(not(feature = "alloc"))]
+fn with_heap_c_path<T, F>(_: &[u8], _: F) -> Result<T, StatxError>
+where
+    F: FnOnce(&CStr) -> Result<T, StatxError>,
+{
+    Err(StatxError::NameTooLong)
+}
+
+/// Safe wrapper of `statx()` for a byte path relative to the current working
+/// directory, see `with_c_path`.
+pub fn statx_bytes(path: &[u8], flags: c_int, mask: c_uint) -> Result<statx, StatxError> {
+    with_c_path(path, |path| crate::statx_path(path, flags, mask))
+}
+
+impl StatxRequest {
+    /// Run the request on a byte path relative to the current working
+    /// directory, see `with_c_path`.
+    pub fn path_bytes(&self, path: &[u8]) -> Result<statx, StatxError> {
+        with_c_path(path, |path| self.path(path))
+    }
+
+    /// Run the request on a byte path relative to the directory `dirfd`, see
+    /// `with_c_path`.
+    pub fn at_bytes(&self, dirfd: c_int, path: &[u8]) -> Result<statx, StatxError> {
+        with_c_path(path, |path| self.at(dirfd, path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn len_of(path: &[u8]) -> Result<usize, StatxError> {
+        with_c_path(path, |c_path| Ok(c_path.to_bytes().len()))
+    }
+
+    #[test]
+    fn c_path() {
+        assert_eq!(len_of(b"/etc/hosts"), Ok(10));
+        assert_eq!(len_of(b""), Ok(0));
+        assert_eq!(len_of(b"/etc\0/hosts"), Err(StatxError::InteriorNul));
+        assert_eq!(len_of(&[b'a'; PATH_MAX - 1]), Ok(PATH_MAX - 1));
+    }
+
+    #[test]
+    fn long_path() {
+        let path = [b'a'; PATH_MAX];
+        if cfg!(feature = "alloc") {
+            assert_eq!(len_of(&path), Ok(PATH_MAX));
+        } else {
+            assert_eq!(len_of(&path), Err(StatxError::NameTooLong));
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! One-time probe of what the running kernel supports.
+
+use crate::{statx_at, StatxMask, AT_FDCWD, STATX__RESERVED};
+use crate::{STATX_ALL, STATX_DIOALIGN, STATX_DIO_READ_ALIGN, STATX_MNT_ID};
+use crate::{STATX_MNT_ID_UNIQUE, STATX_SUBVOL, STATX_WRITE_ATOMIC};
+use core::ffi::CStr;
+use core::fmt;
+use core::mem;
+use core::sync::atomic::{AtomicU32, Ordering};
+use libc::c_uint;
+
+/// Version of the running kernel, as reported by `uname()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KernelVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl KernelVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        KernelVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse the leading `major.minor.patch` of a release string like
+    /// `6.8.0-45-generic`. Missing components are 0.
+    pub fn parse(release: &[u8]) -> Option<Self> {
+        let mut parts = [0u32; 3];
+        let mut rest = release;
+        for (i, part) in parts.iter_mut().enumerate() {
+            let len = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+            if len == 0 {
+                if i == 0 {
+                    return None;
+                }
+                break;
+            }
+            for &c in &rest[..len] {
+                *part = part.checked_mul(10)?.checked_add(u32::from(c - b'0'))?;
+            }
+            rest = &rest[len..];
+            match rest.split_first() {
+                Some((b'.', tail)) => rest = tail,
+                _ => break,
+            }
+        }
+        Some(KernelVersion::new(parts[0], parts[1], parts[2]))
+    }
+
+    /// The `STATX_*` bits known by this kernel version.
+    pub fn statx_mask(&self) -> StatxMask {
+        const TABLE: &[(KernelVersion, c_uint)] = &[
+            (KernelVersion::new(4, 11, 0), STATX_ALL),
+            (KernelVersion::new(5, 8, 0), STATX_MNT_ID),
+            (KernelVersion::new(6, 1, 0), STATX_DIOALIGN),
+            (KernelVersion::new(6, 8, 0), STATX_MNT_ID_UNIQUE),
+            (KernelVersion::new(6, 10, 0), STATX_SUBVOL),
+            (KernelVersion::new(6, 11, 0), STATX_WRITE_ATOMIC),
+            (KernelVersion::new(6, 14, 0), STATX_DIO_READ_ALIGN),
+        ];
+        let bits = TABLE
+            .iter()
+            .filter(|(since, _)| self >= since)
+            .fold(0, |bits, (_, bit)| bits | bit);
+        StatxMask::from_bits_truncate(bits)
+    }
+}
+
+impl fmt::Display for KernelVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// What the running kernel supports, see `kernel_support`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KernelSupport {
+    /// Whether `statx()` can be called at all. It is not the case before
+    /// Linux 4.11, or when blocked by a seccomp filter.
+    pub available: bool,
+    /// The kernel version, if it could be parsed.
+    pub version: Option<KernelVersion>,
+    /// The `STATX_*` bits the kernel recognizes. Filesystems may still not
+    /// fill in all of them.
+    ///
+    /// This is estimated from the kernel version with
+    /// `KernelVersion::statx_mask`, plus the bits returned by a `statx()` of
+    /// `/` asking for everything. Bits backported to an older version, e.g. by
+    /// distribution kernels, are missing unless the root filesystem fills them
+    /// in. Empty if `statx()` is unavailable.
+    pub mask: StatxMask,
+}
+
+// Bits of `STATE`.
+const PROBED: u32 = 1 << 0;
+const AVAILABLE: u32 = 1 << 1;
+const HAS_VERSION: u32 = 1 << 2;
+
+static STATE: AtomicU32 = AtomicU32::new(0);
+// `major << 24 | minor << 16 | patch`, see `pack_version`.
+static VERSION: AtomicU32 = AtomicU32::new(0);
+static MASK: AtomicU32 = AtomicU32::new(0);
+
+/// Probe whether `statx()` is available and which mask bits the kernel
+/// recognizes.
+///
+/// The probe runs on the first call, further calls return the cached result.
+/// Concurrent first calls may probe more than once, with the same result.
+pub fn kernel_support() -> KernelSupport {
+    let mut state = STATE.load(Ordering::Acquire);
+    if state & PROBED == 0 {
+        let support = probe();
+        state = PROBED;
+        if support.available {
+            state |= AVAILABLE;
+        }
+        if let Some(version) = support.version {
+            state |= HAS_VERSION;
+            VERSION.store(pack_version(version), Ordering::Relaxed);
+        }
+        MASK.store(support.mask.bits(), Ordering::Relaxed);
+        STATE.store(state, Ordering::Release);
+    }
+
+    KernelSupport {
+        available: state & AVAILABLE != 0,
+        version: if state & HAS_VERSION != 0 {
+            Some(unpack_version(VERSION.load(Ordering::Relaxed)))
+        } else {
+            None
+        },
+        mask: StatxMask::from_bits_truncate(MASK.load(Ordering::Relaxed)),
+    }
+}
+
+// Components are saturated, which is fine for comparisons with the versions
+// of `KernelVersion::statx_mask`.
+fn pack_version(v: KernelVersion) -> u32 {
+    v.major.min(0xff) << 24 | v.minor.min(0xff) << 16 | v.patch.min(0xffff)
+}
+
+fn unpack_version(v: u32) -> KernelVersion {
+    KernelVersion::new(v >> 24, (v >> 16) & 0xff, v & 0xffff)
+}
+
+fn probe() -> KernelSupport {
+    let root = unsafe { CStr::from_bytes_with_nul_unchecked(b"/\0") };
+    // Unknown bits are ignored by the kernel, the reply has those it filled.
+    let (available, returned) = match statx_at(AT_FDCWD, root, 0, !STATX__RESERVED) {
+        Ok(buf) => (true, buf.mask()),
+        Err(err) => (!err.is_unavailable(), StatxMask::empty()),
+    };
+
+    let mut uts = unsafe { mem::zeroed::<libc::utsname>() };
+    let version = if unsafe { libc::uname(&mut uts) } == 0 {
+        let release = unsafe { CStr::from_ptr(uts.release.as_ptr()) };
+        KernelVersion::parse(release.to_bytes())
+    } else {
+        None
+    };
+
+    let mask = match (available, version) {
+        (false, _) => StatxMask::empty(),
+        (true, Some(version)) => version.statx_mask() | returned,
+        (true, None) => StatxMask::ALL | returned,
+    };
+    KernelSupport {
+        available,
+        version,
+        mask,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version() {
+        let parse = KernelVersion::parse;
+        assert_eq!(
+            parse(b"6.8.0-45-generic"),
+            Some(KernelVersion::new(6, 8, 0))
+        );
+        assert_eq!(parse(b"4.19.112+"), Some(KernelVersion::new(4, 19, 112)));
+        assert_eq!(parse(b"5.10"), Some(KernelVersion::new(5, 10, 0)));
+        assert_eq!(parse(b"6.1-rc1"), Some(KernelVersion::new(6, 1, 0)));
+        assert_eq!(parse(b"generic"), None);
+    }
+
+    #[test]
+    fn version_mask() {
+        let mask = |major, minor| KernelVersion::new(major, minor, 0).statx_mask();
+        assert_eq!(mask(4, 4), StatxMask::empty());
+        assert_eq!(mask(4, 11), StatxMask::ALL);
+        assert_eq!(mask(5, 10), StatxMask::ALL | StatxMask::MNT_ID);
+        assert!(mask(6, 10).contains(StatxMask::SUBVOL));
+        assert!(!mask(6, 10).contains(StatxMask::WRITE_ATOMIC));
+    }
+}
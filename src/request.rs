@@ -0,0 +1,212 @@
+//! Builder of `statx()` calls.
+
+use crate::stat::fstatat_at;
+use crate::{statx, statx_into, StatxError, StatxMask};
+use crate::{AT_EMPTY_PATH, AT_FDCWD, AT_NO_AUTOMOUNT, AT_SYMLINK_NOFOLLOW};
+use crate::{AT_STATX_DONT_SYNC, AT_STATX_FORCE_SYNC, AT_STATX_SYNC_AS_STAT};
+use core::ffi::CStr;
+use core::mem;
+use libc::{c_int, c_uint};
+
+/// What to do about synchronising with the server on network filesystems.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SyncMode {
+    /// `AT_STATX_SYNC_AS_STAT`: do whatever `stat()` does.
+    #[default]
+    AsStat,
+    /// `AT_STATX_FORCE_SYNC`: force the attributes to be synchronised with the
+    /// server.
+    ForceSync,
+    /// `AT_STATX_DONT_SYNC`: don't synchronise anything, just take whatever
+    /// the system has cached.
+    DontSync,
+}
+
+impl SyncMode {
+    fn flags(self) -> c_uint {
+        match self {
+            SyncMode::AsStat => AT_STATX_SYNC_AS_STAT,
+            SyncMode::ForceSync => AT_STATX_FORCE_SYNC,
+            SyncMode::DontSync => AT_STATX_DONT_SYNC,
+        }
+    }
+}
+
+/// Builder of the `flags` and `mask` arguments of `statx()`.
+///
+/// By default, symlinks are followed, automounts are triggered, the sync
+/// behaviour is that of `stat()` and `STATX_BASIC_STATS` is requested.
+///
+/// ```no_run
+/// use statx_sys::{StatxMask, StatxRequest, SyncMode};
+///
+/// let path = std::ffi::CString::new("/etc/hosts").unwrap();
+/// let buf = StatxRequest::new()
+///     .mask(StatxMask::BASIC_STATS | StatxMask::BTIME)
+///     .follow_symlinks(false)
+///     .sync(SyncMode::DontSync)
+///     .path(&path)
+///     .unwrap();
+/// println!("{:?}", buf);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StatxRequest {
+    mask: StatxMask,
+    follow_symlinks: bool,
+    automount: bool,
+    sync: SyncMode,
+    fallback: bool,
+}
+
+impl Default for StatxRequest {
+    fn default() -> Self {
+        StatxRequest::new()
+    }
+}
+
+impl StatxRequest {
+    /// Create a request with default options.
+    pub const fn new() -> Self {
+        StatxRequest {
+            mask: StatxMask::BASIC_STATS,
+            follow_symlinks: true,
+            automount: true,
+            sync: SyncMode::AsStat,
+            fallback: false,
+        }
+    }
+
+    /// Set the fields to request.
+    ///
+    /// `StatxMask` cannot hold `STATX__RESERVED`, so the request never carries
+    /// the reserved bit.
+    pub fn mask(mut self, mask: StatxMask) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Whether to follow a trailing symlink, instead of returning information
+    /// about the link itself (`AT_SYMLINK_NOFOLLOW`).
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Whether to not trigger the automount of a terminal automount point
+    /// (`AT_NO_AUTOMOUNT`).
+    pub fn no_automount(mut self, no_automount: bool) -> Self {
+        self.automount = !no_automount;
+        self
+    }
+
+    /// Set the synchronisation behaviour.
+    pub fn sync(mut self, sync: SyncMode) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// Whether to fall back to `fstatat()` when `statx()` is unavailable, see
+    /// `StatxError::is_unavailable`.
+    ///
+    /// The result then only has `STATX_BASIC_STATS` in `stx_mask`, whatever
+    /// the requested mask, see `statx::from_stat`.
+    pub fn fallback(mut self, fallback: bool) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Whether `fallback` is set.
+    #[cfg(all(feature = "async", feature = "io-uring"))]
+    pub(crate) fn has_fallback(&self) -> bool {
+        self.fallback
+    }
+
+    /// The `flags` argument of `statx()`.
+    pub fn flags_bits(&self) -> c_int {
+        let mut flags = self.sync.flags();
+        if !self.follow_symlinks {
+            flags |= AT_SYMLINK_NOFOLLOW;
+        }
+        if !self.automount {
+            flags |= AT_NO_AUTOMOUNT;
+        }
+        flags as c_int
+    }
+
+    /// The `mask` argument of `statx()`.
+    pub fn mask_bits(&self) -> c_uint {
+        self.mask.bits()
+    }
+
+    /// Run the request on a path relative to the current working directory.
+    pub fn path(&self, path: &CStr) -> Result<statx, StatxError> {
+        self.at(AT_FDCWD, path)
+    }
+
+    /// Run the request on an open file descriptor, using `AT_EMPTY_PATH`.
+    pub fn fd(&self, fd: c_int) -> Result<statx, StatxError> {
+        let empty = unsafe { CStr::from_bytes_with_nul_unchecked(b"\0") };
+        self.run(fd, empty, self.flags_bits() | AT_EMPTY_PATH as c_int)
+    }
+
+    /// Run the request on a path relative to the directory `dirfd`.
+    ///
+    /// Absolute paths ignore `dirfd`, and `AT_FDCWD` refers to the
+    /// current working directory.
+    pub fn at(&self, dirfd: c_int, path: &CStr) -> Result<statx, StatxError> {
+        self.run(dirfd, path, self.flags_bits())
+    }
+
+    /// Like `at`, but into an existing buffer.
+    #[cfg(feature = "std")]
+    pub(crate) fn at_into(
+        &self,
+        dirfd: c_int,
+        path: &CStr,
+        buf: &mut statx,
+    ) -> Result<(), StatxError> {
+        self.run_into(dirfd, path, self.flags_bits(), buf)
+    }
+
+    fn run(&self, dirfd: c_int, path: &CStr, flags: c_int) -> Result<statx, StatxError> {
+        let mut buf = unsafe { mem::zeroed::<statx>() };
+        self.run_into(dirfd, path, flags, &mut buf).map(|()| buf)
+    }
+
+    fn run_into(
+        &self,
+        dirfd: c_int,
+        path: &CStr,
+        flags: c_int,
+        buf: &mut statx,
+    ) -> Result<(), StatxError> {
+        match statx_into(dirfd, path, flags, self.mask_bits(), buf) {
+            Err(err) if self.fallback && err.is_unavailable() => {
+                *buf = fstatat_at(dirfd, path, flags)?;
+                Ok(())
+            }
+            ret => ret,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_flags() {
+        let req = StatxRequest::new();
+        assert_eq!(req.flags_bits(), 0);
+        assert_eq!(req.mask_bits(), crate::STATX_BASIC_STATS);
+
+        let req = req
+            .follow_symlinks(false)
+            .no_automount(true)
+            .sync(SyncMode::ForceSync);
+        assert_eq!(
+            req.flags_bits(),
+            (AT_SYMLINK_NOFOLLOW | AT_NO_AUTOMOUNT | AT_STATX_FORCE_SYNC) as c_int
+        );
+    }
+}
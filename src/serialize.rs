@@ -0,0 +1,289 @@
+//! `serde` support for `statx` and `statx_timestamp`, with the `serde` feature.
+//!
+//! A `statx` is a map of its fields without the `stx_` prefix, where fields
+//! not filled in according to `stx_mask` are left out, e.g.
+//! `{"mask": ["TYPE", "MODE"], "blksize": 4096, "attributes": [], ...,
+//! "mode": 16877, ...}`. Missing fields are zero when deserialized.
+//!
+//! Fields whose bit is clear are still written if they are not zero, so that
+//! the round trip is lossless: the kernel installs values in the
+//! `STATX_BASIC_STATS` fields for compatibility even when it clears their
+//! bits, e.g. the uid and gid made up by CIFS. The spare words are written
+//! likewise when not zero, so that fields added by newer kernels survive.
+
+use crate::{statx, statx_timestamp, StatxAttributes, StatxMask};
+use crate::{STATX_ATIME, STATX_BLOCKS, STATX_BTIME, STATX_CTIME, STATX_DIOALIGN};
+use crate::{STATX_DIO_READ_ALIGN, STATX_GID, STATX_INO, STATX_MNT_ID, STATX_MNT_ID_UNIQUE};
+use crate::{STATX_MODE, STATX_MTIME, STATX_NLINK, STATX_SIZE, STATX_SUBVOL};
+use crate::{STATX_UID, STATX_WRITE_ATOMIC};
+use core::mem;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "statx_timestamp")]
+struct Timestamp {
+    sec: i64,
+    nsec: u32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    reserved: i32,
+}
+
+impl From<statx_timestamp> for Timestamp {
+    fn from(ts: statx_timestamp) -> Self {
+        Timestamp {
+            sec: ts.tv_sec,
+            nsec: ts.tc_nsec,
+            reserved: ts.__reserved,
+        }
+    }
+}
+
+impl From<Timestamp> for statx_timestamp {
+    fn from(ts: Timestamp) -> Self {
+        statx_timestamp {
+            tv_sec: ts.sec,
+            tc_nsec: ts.nsec,
+            __reserved: ts.reserved,
+        }
+    }
+}
+
+impl Serialize for statx_timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Timestamp::from(*self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for statx_timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Timestamp::deserialize(deserializer).map(statx_timestamp::from)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "statx")]
+struct Statx {
+    mask: StatxMask,
+    blksize: u32,
+    attributes: StatxAttributes,
+    attributes_mask: StatxAttributes,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nlink: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    uid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mode: Option<u16>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    spare0: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ino: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    blocks: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    atime: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    btime: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ctime: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mtime: Option<Timestamp>,
+    rdev_major: u32,
+    rdev_minor: u32,
+    dev_major: u32,
+    dev_minor: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mnt_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dio_mem_align: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dio_offset_align: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    subvol: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    atomic_write_unit_min: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    atomic_write_unit_max: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    atomic_write_segments_max: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dio_read_offset_align: Option<u32>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    spare2: [u64; 9],
+}
+
+fn is_zero<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// A field, written if its bit is set in `stx_mask`, or if there is a value to
+/// keep anyway.
+fn field<T: Default + PartialEq>(stx_mask: u32, mask: u32, value: T) -> Option<T> {
+    if stx_mask & mask != 0 || !is_zero(&value) {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+impl Serialize for statx {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let m = self.stx_mask;
+        Statx {
+            mask: StatxMask::from_bits_truncate(self.stx_mask),
+            blksize: self.stx_blksize,
+            attributes: StatxAttributes::from_bits(self.stx_attributes),
+            attributes_mask: StatxAttributes::from_bits(self.stx_attributes_mask),
+            nlink: field(m, STATX_NLINK, self.stx_nlink),
+            uid: field(m, STATX_UID, self.stx_uid),
+            gid: field(m, STATX_GID, self.stx_gid),
+            mode: field(m, STATX_MODE, self.stx_mode),
+            spare0: self.__spare0[0],
+            ino: field(m, STATX_INO, self.stx_ino),
+            size: field(m, STATX_SIZE, self.stx_size),
+            blocks: field(m, STATX_BLOCKS, self.stx_blocks),
+            atime: field(m, STATX_ATIME, Timestamp::from(self.stx_atime)),
+            btime: field(m, STATX_BTIME, Timestamp::from(self.stx_btime)),
+            ctime: field(m, STATX_CTIME, Timestamp::from(self.stx_ctime)),
+            mtime: field(m, STATX_MTIME, Timestamp::from(self.stx_mtime)),
+            rdev_major: self.stx_rdev_major,
+            rdev_minor: self.stx_rdev_minor,
+            dev_major: self.stx_dev_major,
+            dev_minor: self.stx_dev_minor,
+            mnt_id: field(m, STATX_MNT_ID | STATX_MNT_ID_UNIQUE, self.stx_mnt_id),
+            dio_mem_align: field(m, STATX_DIOALIGN, self.stx_dio_mem_align),
+            dio_offset_align: field(m, STATX_DIOALIGN, self.stx_dio_offset_align),
+            subvol: field(m, STATX_SUBVOL, self.stx_subvol),
+            atomic_write_unit_min: field(m, STATX_WRITE_ATOMIC, self.stx_atomic_write_unit_min),
+            atomic_write_unit_max: field(m, STATX_WRITE_ATOMIC, self.stx_atomic_write_unit_max),
+            atomic_write_segments_max: field(
+                m,
+                STATX_WRITE_ATOMIC,
+                self.stx_atomic_write_segments_max,
+            ),
+            dio_read_offset_align: field(m, STATX_DIO_READ_ALIGN, self.stx_dio_read_offset_align),
+            spare2: self.__spare2,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for statx {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = Statx::deserialize(deserializer)?;
+        let mut buf: statx = unsafe { mem::zeroed() };
+        buf.stx_mask = s.mask.bits();
+        buf.stx_blksize = s.blksize;
+        buf.stx_attributes = s.attributes.bits();
+        buf.stx_attributes_mask = s.attributes_mask.bits();
+        buf.stx_nlink = s.nlink.unwrap_or(0);
+        buf.stx_uid = s.uid.unwrap_or(0);
+        buf.stx_gid = s.gid.unwrap_or(0);
+        buf.stx_mode = s.mode.unwrap_or(0);
+        buf.__spare0 = [s.spare0];
+        buf.stx_ino = s.ino.unwrap_or(0);
+        buf.stx_size = s.size.unwrap_or(0);
+        buf.stx_blocks = s.blocks.unwrap_or(0);
+        buf.stx_atime = s.atime.unwrap_or_default().into();
+        buf.stx_btime = s.btime.unwrap_or_default().into();
+        buf.stx_ctime = s.ctime.unwrap_or_default().into();
+        buf.stx_mtime = s.mtime.unwrap_or_default().into();
+        buf.stx_rdev_major = s.rdev_major;
+        buf.stx_rdev_minor = s.rdev_minor;
+        buf.stx_dev_major = s.dev_major;
+        buf.stx_dev_minor = s.dev_minor;
+        buf.stx_mnt_id = s.mnt_id.unwrap_or(0);
+        buf.stx_dio_mem_align = s.dio_mem_align.unwrap_or(0);
+        buf.stx_dio_offset_align = s.dio_offset_align.unwrap_or(0);
+        buf.stx_subvol = s.subvol.unwrap_or(0);
+        buf.stx_atomic_write_unit_min = s.atomic_write_unit_min.unwrap_or(0);
+        buf.stx_atomic_write_unit_max = s.atomic_write_unit_max.unwrap_or(0);
+        buf.stx_atomic_write_segments_max = s.atomic_write_segments_max.unwrap_or(0);
+        buf.stx_dio_read_offset_align = s.dio_read_offset_align.unwrap_or(0);
+        buf.__spare2 = s.spare2;
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{STATX_ATTR_APPEND, STATX_ATTR_IMMUTABLE, STATX_TYPE};
+
+    fn sample() -> statx {
+        let mut buf: statx = unsafe { mem::zeroed() };
+        buf.stx_mask = STATX_TYPE | STATX_MODE | STATX_BTIME | STATX_MNT_ID;
+        buf.stx_blksize = 4096;
+        buf.stx_attributes = STATX_ATTR_IMMUTABLE | (1 << 40);
+        buf.stx_attributes_mask = STATX_ATTR_IMMUTABLE | STATX_ATTR_APPEND;
+        buf.stx_mode = 0o100644;
+        buf.stx_btime = statx_timestamp::new(-5, 7).unwrap();
+        buf.stx_dev_major = 8;
+        buf.stx_dev_minor = 1;
+        buf.stx_mnt_id = 42;
+        buf
+    }
+
+    #[test]
+    fn compatibility_values() {
+        // Like CIFS, without `STATX_UID` and `STATX_GID` but with values.
+        let mut buf = sample();
+        buf.stx_uid = 1000;
+        buf.stx_mask &= !STATX_MODE;
+        let json = serde_json::to_value(buf).unwrap();
+        assert_eq!(json["uid"], 1000);
+        assert_eq!(json["mode"], 0o100644);
+        assert!(json.get("gid").is_none());
+
+        let back: statx = serde_json::from_value(json).unwrap();
+        assert_eq!((back.stx_uid, back.stx_gid), (1000, 0));
+        assert_eq!(back.stx_mode, 0o100644);
+        assert_eq!(back.mode(), None);
+    }
+
+    #[test]
+    fn json() {
+        let json = serde_json::to_value(sample()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "mask": ["TYPE", "MODE", "BTIME", "MNT_ID"],
+                "blksize": 4096,
+                "attributes": ["IMMUTABLE", 1u64 << 40],
+                "attributes_mask": ["IMMUTABLE", "APPEND"],
+                "mode": 0o100644,
+                "btime": {"sec": -5, "nsec": 7},
+                "rdev_major": 0,
+                "rdev_minor": 0,
+                "dev_major": 8,
+                "dev_minor": 1,
+                "mnt_id": 42,
+            })
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut buf = sample();
+        buf.stx_btime.__reserved = -1;
+        buf.__spare2[8] = 0xdead_beef;
+        let json = serde_json::to_string(&buf).unwrap();
+        assert!(json.contains("\"spare2\":[0,0,0,0,0,0,0,0,3735928559]"));
+        let back: statx = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.stx_btime.__reserved, -1);
+        assert_eq!(back.__spare2, buf.__spare2);
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+    }
+
+    #[test]
+    fn bit_names() {
+        let mask: StatxMask = serde_json::from_str(r#"["type", "BTIME", 4096]"#).unwrap();
+        assert_eq!(mask, StatxMask::TYPE | StatxMask::BTIME | StatxMask::MNT_ID);
+        assert!(serde_json::from_str::<StatxMask>(r#"["NOPE"]"#).is_err());
+        assert!(serde_json::from_str::<StatxMask>("[2147483648]").is_err());
+        assert!(serde_json::from_str::<StatxMask>("[4294967296]").is_err());
+    }
+}
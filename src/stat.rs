@@ -0,0 +1,226 @@
+//! Conversions between `statx` and `struct stat`.
+
+use crate::dev::{major, makedev, minor};
+use crate::{statx, statx_timestamp, StatxError, STATX_BASIC_STATS};
+use crate::{AT_EMPTY_PATH, AT_NO_AUTOMOUNT, AT_SYMLINK_NOFOLLOW};
+use core::convert::TryFrom;
+use core::ffi::CStr;
+use core::mem;
+use libc::c_int;
+
+/// Fill a `statx` from a `struct stat` or `struct stat64`, whose fields have
+/// the same names.
+macro_rules! from_stat {
+    ($st:expr) => {{
+        let st = $st;
+        let mut buf = unsafe { mem::zeroed::<statx>() };
+        buf.stx_mask = STATX_BASIC_STATS;
+        buf.stx_blksize = st.st_blksize as u32;
+        buf.stx_nlink = st.st_nlink as u32;
+        buf.stx_uid = st.st_uid;
+        buf.stx_gid = st.st_gid;
+        buf.stx_mode = st.st_mode as u16;
+        buf.stx_ino = st.st_ino as u64;
+        buf.stx_size = st.st_size as u64;
+        buf.stx_blocks = st.st_blocks as u64;
+        buf.stx_atime = timestamp(st.st_atime as i64, st.st_atime_nsec as i64);
+        buf.stx_ctime = timestamp(st.st_ctime as i64, st.st_ctime_nsec as i64);
+        buf.stx_mtime = timestamp(st.st_mtime as i64, st.st_mtime_nsec as i64);
+        buf.stx_rdev_major = major(st.st_rdev as u64);
+        buf.stx_rdev_minor = minor(st.st_rdev as u64);
+        buf.stx_dev_major = major(st.st_dev as u64);
+        buf.stx_dev_minor = minor(st.st_dev as u64);
+        buf
+    }};
+}
+
+/// Build a `struct stat` or `struct stat64` from a `statx`.
+macro_rules! to_stat {
+    ($buf:expr, $ty:ty) => {{
+        let buf = $buf;
+        let mut st = unsafe { mem::zeroed::<$ty>() };
+        st.st_dev = makedev(buf.stx_dev_major, buf.stx_dev_minor) as _;
+        st.st_ino = fit(buf.stx_ino)?;
+        st.st_nlink = fit(buf.stx_nlink)?;
+        st.st_mode = buf.stx_mode as _;
+        st.st_uid = buf.stx_uid;
+        st.st_gid = buf.stx_gid;
+        st.st_rdev = makedev(buf.stx_rdev_major, buf.stx_rdev_minor) as _;
+        st.st_size = fit(buf.stx_size)?;
+        st.st_blksize = buf.stx_blksize as _;
+        st.st_blocks = fit(buf.stx_blocks)?;
+        st.st_atime = fit(buf.stx_atime.tv_sec)?;
+        st.st_atime_nsec = buf.stx_atime.tc_nsec as _;
+        st.st_mtime = fit(buf.stx_mtime.tv_sec)?;
+        st.st_mtime_nsec = buf.stx_mtime.tc_nsec as _;
+        st.st_ctime = fit(buf.stx_ctime.tv_sec)?;
+        st.st_ctime_nsec = buf.stx_ctime.tc_nsec as _;
+        Ok(st)
+    }};
+}
+
+impl statx {
+    /// Synthesize a `statx` from the result of `stat()` and co.
+    ///
+    /// Only `STATX_BASIC_STATS` is set in `stx_mask`, and the device numbers
+    /// are split into major and minor.
+    #[allow(clippy::unnecessary_cast)] // Field types differ between targets.
+    pub fn from_stat(st: &libc::stat) -> Self {
+        from_stat!(st)
+    }
+
+    /// Convert to a `struct stat`, as `stat()` would have returned it.
+    ///
+    /// Like `stat()`, this fails with `EOVERFLOW` if the inode number, size,
+    /// block count, link count or a time does not fit in the field of the
+    /// target, e.g. for files over 2 GiB on 32-bit glibc targets, see
+    /// `to_stat64` there. Fields missing from `stx_mask` are copied as is,
+    /// since the kernel fills in `STATX_BASIC_STATS` fields in any case for the
+    /// sake of this emulation.
+    pub fn to_stat(&self) -> Result<libc::stat, StatxError> {
+        to_stat!(self, libc::stat)
+    }
+
+    /// Convert to a `struct stat64`, as `stat64()` would have returned it.
+    ///
+    /// Sizes and inode numbers are 64 bits wide, unlike in `struct stat` on
+    /// 32-bit glibc targets. See `to_stat`.
+    #[cfg(all(target_env = "gnu", target_pointer_width = "32"))]
+    pub fn to_stat64(&self) -> Result<libc::stat64, StatxError> {
+        to_stat!(self, libc::stat64)
+    }
+}
+
+/// Convert `value` to the type of a `struct stat` field, or fail with
+/// `EOVERFLOW`.
+fn fit<T: TryFrom<U>, U>(value: U) -> Result<T, StatxError> {
+    T::try_from(value).map_err(|_| StatxError::Other(libc::EOVERFLOW))
+}
+
+fn timestamp(sec: i64, nsec: i64) -> statx_timestamp {
+    statx_timestamp {
+        tv_sec: sec,
+        tc_nsec: nsec as u32,
+        __reserved: 0,
+    }
+}
+
+// `struct stat` has 32-bit sizes, inode numbers and times on 32-bit glibc
+// targets, where `fstatat()` fails with `EOVERFLOW` for large files.
+#[cfg(not(all(target_env = "gnu", target_pointer_width = "32")))]
+use libc::{fstat, fstatat, stat as stat_buf};
+#[cfg(all(target_env = "gnu", target_pointer_width = "32"))]
+use libc::{fstat64 as fstat, fstatat64 as fstatat, stat64 as stat_buf};
+
+/// `fstatat()`, or `fstat()` for an empty path with `AT_EMPTY_PATH`, with the
+/// result converted as by `statx::from_stat`.
+///
+/// The `AT_STATX_*` sync flags have no equivalent and are ignored.
+#[allow(clippy::unnecessary_cast)]
+pub(crate) fn fstatat_at(dirfd: c_int, path: &CStr, flags: c_int) -> Result<statx, StatxError> {
+    let flags = flags & (AT_SYMLINK_NOFOLLOW | AT_NO_AUTOMOUNT | AT_EMPTY_PATH) as c_int;
+    let mut st = unsafe { mem::zeroed::<stat_buf>() };
+    let ret = unsafe {
+        if path.to_bytes().is_empty() && flags & AT_EMPTY_PATH as c_int != 0 {
+            fstat(dirfd, &mut st)
+        } else {
+            fstatat(dirfd, path.as_ptr(), &mut st, flags)
+        }
+    };
+    if ret == 0 {
+        Ok(from_stat!(&st))
+    } else {
+        Err(StatxError::last())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{statx_at, AT_FDCWD, AT_STATX_FORCE_SYNC};
+
+    fn assert_same(fallback: &statx, buf: &statx) {
+        assert_eq!(fallback.stx_mask, STATX_BASIC_STATS);
+        assert_eq!(fallback.stx_mode, buf.stx_mode);
+        assert_eq!(fallback.stx_ino, buf.stx_ino);
+        assert_eq!(fallback.stx_size, buf.stx_size);
+        assert_eq!(fallback.stx_nlink, buf.stx_nlink);
+        assert_eq!(fallback.stx_mtime, buf.stx_mtime);
+        assert_eq!(fallback.dev_id(), buf.dev_id());
+    }
+
+    #[test]
+    fn fstatat_fallback() {
+        extern crate std;
+        use std::os::unix::ffi::OsStrExt;
+        use std::{env, ffi::CString, fs, os::unix::fs::symlink, process};
+
+        let dir = env::temp_dir().join(std::format!("statx-sys-stat-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        let link = dir.join("link");
+        symlink("does-not-exist", &link).unwrap();
+        let link = CString::new(link.as_os_str().as_bytes()).unwrap();
+
+        // The sync flags are dropped, `fstatat()` rejects them with `EINVAL`.
+        let flags = (AT_STATX_FORCE_SYNC | AT_SYMLINK_NOFOLLOW) as c_int;
+        let fallback = fstatat_at(AT_FDCWD, &link, flags).unwrap();
+        let buf = statx_at(AT_FDCWD, &link, flags, STATX_BASIC_STATS).unwrap();
+        assert_eq!(
+            fallback.stx_mode & libc::S_IFMT as u16,
+            libc::S_IFLNK as u16
+        );
+        assert_same(&fallback, &buf);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let path = CStr::from_bytes_with_nul(b"Cargo.toml\0").unwrap();
+        let fd = unsafe { libc::open(path.as_ptr(), libc::O_RDONLY | libc::O_CLOEXEC) };
+        assert!(fd >= 0);
+        let empty = CStr::from_bytes_with_nul(b"\0").unwrap();
+        let fallback = fstatat_at(fd, empty, AT_EMPTY_PATH as c_int).unwrap();
+        let buf = statx_at(fd, empty, AT_EMPTY_PATH as c_int, STATX_BASIC_STATS).unwrap();
+        assert_same(&fallback, &buf);
+        unsafe { libc::close(fd) };
+    }
+
+    #[test]
+    fn stat_round_trip() {
+        let mut buf = unsafe { mem::zeroed::<statx>() };
+        buf.stx_mask = STATX_BASIC_STATS;
+        buf.stx_blksize = 4096;
+        buf.stx_mode = 0o100644;
+        buf.stx_ino = 1234;
+        buf.stx_size = 5678;
+        buf.stx_mtime = statx_timestamp::new(-5, 999).unwrap();
+        buf.stx_dev_major = 259;
+        buf.stx_dev_minor = 3;
+
+        let st = buf.to_stat().unwrap();
+        assert_eq!(st.st_dev, makedev(259, 3) as libc::dev_t);
+        let back = statx::from_stat(&st);
+        assert_eq!(back.stx_mode, buf.stx_mode);
+        assert_eq!(back.stx_ino, buf.stx_ino);
+        assert_eq!(back.stx_size, buf.stx_size);
+        assert_eq!(back.stx_blksize, buf.stx_blksize);
+        assert_eq!(back.stx_mtime, buf.stx_mtime);
+        assert_eq!(back.dev_id(), buf.dev_id());
+    }
+
+    #[test]
+    fn stat_overflow() {
+        let overflow = Err(StatxError::Other(libc::EOVERFLOW));
+        let mut buf = unsafe { mem::zeroed::<statx>() };
+        buf.stx_size = u64::MAX;
+        assert_eq!(buf.to_stat().map(|_| ()), overflow);
+
+        // 5 GiB, which only fits in `struct stat64` on 32-bit glibc targets.
+        buf.stx_size = 5 << 30;
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(buf.to_stat().unwrap().st_size, 5 << 30);
+        #[cfg(all(target_env = "gnu", target_pointer_width = "32"))]
+        {
+            assert_eq!(buf.to_stat().map(|_| ()), overflow);
+            assert_eq!(buf.to_stat64().unwrap().st_size, 5 << 30);
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! Conversions of `statx_timestamp`.
+
+use crate::statx_timestamp;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Sub;
+use core::time::Duration;
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// A duration which may be negative, with nanosecond precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SignedDuration {
+    nanos: i128,
+}
+
+impl SignedDuration {
+    /// The zero duration.
+    pub const ZERO: SignedDuration = SignedDuration { nanos: 0 };
+
+    /// Create a duration from a number of nanoseconds.
+    pub const fn from_nanos(nanos: i128) -> Self {
+        SignedDuration { nanos }
+    }
+
+    /// Total number of nanoseconds.
+    pub const fn as_nanos(&self) -> i128 {
+        self.nanos
+    }
+
+    /// Whether the duration is below zero.
+    pub const fn is_negative(&self) -> bool {
+        self.nanos < 0
+    }
+
+    /// The absolute value, saturated to `Duration::MAX`.
+    pub fn abs(&self) -> Duration {
+        let nanos = self.nanos.unsigned_abs();
+        let secs = nanos / NANOS_PER_SEC as u128;
+        match u64::try_from(secs) {
+            Ok(secs) => Duration::new(secs, (nanos % NANOS_PER_SEC as u128) as u32),
+            Err(_) => Duration::MAX,
+        }
+    }
+}
+
+impl From<Duration> for SignedDuration {
+    fn from(d: Duration) -> Self {
+        SignedDuration::from_nanos(d.as_nanos() as i128)
+    }
+}
+
+/// Error of the conversions of `statx_timestamp`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimestampError {
+    /// The nanoseconds are above 999,999,999.
+    InvalidNanoseconds(u32),
+    /// The timestamp cannot be represented in the target type.
+    OutOfRange,
+}
+
+impl fmt::Display for TimestampError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TimestampError::InvalidNanoseconds(nsec) => {
+                write!(f, "invalid nanoseconds in timestamp: {}", nsec)
+            }
+            TimestampError::OutOfRange => f.write_str("timestamp out of range"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TimestampError {}
+
+impl statx_timestamp {
+    /// Create a timestamp from seconds and nanoseconds since the epoch.
+    pub fn new(tv_sec: i64, tv_nsec: u32) -> Result<Self, TimestampError> {
+        let ts = statx_timestamp {
+            tv_sec,
+            tc_nsec: tv_nsec,
+            __reserved: 0,
+        };
+        ts.validate().map(|()| ts)
+    }
+
+    /// Create a timestamp from nanoseconds since the epoch.
+    pub fn from_nanos(nanos: i128) -> Result<Self, TimestampError> {
+        let tv_sec = i64::try_from(nanos.div_euclid(NANOS_PER_SEC))
+            .map_err(|_| TimestampError::OutOfRange)?;
+        Ok(statx_timestamp {
+            tv_sec,
+            tc_nsec: nanos.rem_euclid(NANOS_PER_SEC) as u32,
+            __reserved: 0,
+        })
+    }
+
+    /// Check that the nanoseconds are in `0..=999_999_999`.
+    pub fn validate(&self) -> Result<(), TimestampError> {
+        if i128::from(self.tc_nsec) < NANOS_PER_SEC {
+            Ok(())
+        } else {
+            Err(TimestampError::InvalidNanoseconds(self.tc_nsec))
+        }
+    }
+
+    /// Total nanoseconds since the epoch, negative before 1970.
+    pub fn as_nanos(&self) -> Result<i128, TimestampError> {
+        self.validate()?;
+        Ok(self.raw_nanos())
+    }
+
+    /// Duration since the epoch, negative before 1970.
+    ///
+    /// For example, `tv_sec == -1` and `tv_nsec == 250_000_000` is 0.75 second
+    /// before the epoch.
+    pub fn since_epoch(&self) -> Result<SignedDuration, TimestampError> {
+        self.as_nanos().map(SignedDuration::from_nanos)
+    }
+
+    /// Signed duration from `earlier` to `self`, same as `self - earlier`.
+    pub fn duration_since(&self, earlier: statx_timestamp) -> SignedDuration {
+        *self - earlier
+    }
+
+    fn raw_nanos(&self) -> i128 {
+        i128::from(self.tv_sec) * NANOS_PER_SEC + i128::from(self.tc_nsec)
+    }
+
+    /// Round down to a whole second.
+    pub fn truncate_to_secs(&self) -> Self {
+        self.truncate_to(Duration::from_secs(1))
+    }
+
+    /// Round down to a whole microsecond.
+    pub fn truncate_to_micros(&self) -> Self {
+        self.truncate_to(Duration::from_micros(1))
+    }
+
+    /// Round down to a multiple of `granularity` since the epoch, like a
+    /// filesystem with coarser timestamps would store it (e.g. 2 seconds for
+    /// FAT's mtime). A zero granularity returns the timestamp as is.
+    ///
+    /// Timestamps before 1970 are rounded towards the past too, and the result
+    /// saturates at the earliest representable second.
+    pub fn truncate_to(&self, granularity: Duration) -> Self {
+        let step = granularity.as_nanos() as i128;
+        if step == 0 {
+            return *self;
+        }
+        let nanos = self.raw_nanos();
+        statx_timestamp::from_nanos(nanos - nanos.rem_euclid(step)).unwrap_or(statx_timestamp {
+            tv_sec: i64::MIN,
+            tc_nsec: 0,
+            __reserved: 0,
+        })
+    }
+}
+
+// Comparisons, hashing and differences ignore `__reserved`.
+
+impl PartialEq for statx_timestamp {
+    fn eq(&self, other: &Self) -> bool {
+        (self.tv_sec, self.tc_nsec) == (other.tv_sec, other.tc_nsec)
+    }
+}
+
+impl Eq for statx_timestamp {}
+
+impl PartialOrd for statx_timestamp {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for statx_timestamp {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.tv_sec, self.tc_nsec).cmp(&(other.tv_sec, other.tc_nsec))
+    }
+}
+
+impl Hash for statx_timestamp {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.tv_sec.hash(state);
+        self.tc_nsec.hash(state);
+    }
+}
+
+impl Sub for statx_timestamp {
+    type Output = SignedDuration;
+
+    fn sub(self, rhs: Self) -> SignedDuration {
+        SignedDuration::from_nanos(self.raw_nanos() - rhs.raw_nanos())
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<statx_timestamp> for std::time::SystemTime {
+    type Error = TimestampError;
+
+    fn try_from(ts: statx_timestamp) -> Result<Self, Self::Error> {
+        let since = ts.since_epoch()?;
+        let epoch = std::time::UNIX_EPOCH;
+        if since.is_negative() {
+            epoch.checked_sub(since.abs())
+        } else {
+            epoch.checked_add(since.abs())
+        }
+        .ok_or(TimestampError::OutOfRange)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<std::time::SystemTime> for statx_timestamp {
+    type Error = TimestampError;
+
+    fn try_from(time: std::time::SystemTime) -> Result<Self, Self::Error> {
+        let nanos = match time.duration_since(std::time::UNIX_EPOCH) {
+            Ok(d) => d.as_nanos() as i128,
+            Err(err) => -(err.duration().as_nanos() as i128),
+        };
+        statx_timestamp::from_nanos(nanos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_timestamp() {
+        let ts = statx_timestamp::new(-1, 250_000_000).unwrap();
+        assert_eq!(ts.as_nanos(), Ok(-750_000_000));
+        let since = ts.since_epoch().unwrap();
+        assert!(since.is_negative());
+        assert_eq!(since.abs(), Duration::from_millis(750));
+
+        let back = statx_timestamp::from_nanos(-750_000_000).unwrap();
+        assert_eq!((back.tv_sec, back.tc_nsec), (-1, 250_000_000));
+    }
+
+    #[test]
+    fn invalid_nanoseconds() {
+        let err = TimestampError::InvalidNanoseconds(1_000_000_000);
+        assert_eq!(statx_timestamp::new(0, 1_000_000_000).unwrap_err(), err);
+        assert!(statx_timestamp::new(0, 999_999_999).is_ok());
+    }
+
+    #[test]
+    fn compare() {
+        let a = statx_timestamp::new(-1, 999_999_999).unwrap();
+        let b = statx_timestamp::new(0, 0).unwrap();
+        let mut c = statx_timestamp::new(0, 0).unwrap();
+        c.__reserved = 1;
+        assert!(a < b);
+        assert_eq!(b, c);
+        assert_eq!(b - a, SignedDuration::from_nanos(1));
+        assert_eq!(a.duration_since(b), SignedDuration::from_nanos(-1));
+    }
+
+    #[test]
+    fn truncate() {
+        let ts = statx_timestamp::new(3, 123_456_789).unwrap();
+        assert_eq!(ts.truncate_to_secs(), statx_timestamp::new(3, 0).unwrap());
+        assert_eq!(
+            ts.truncate_to_micros(),
+            statx_timestamp::new(3, 123_456_000).unwrap()
+        );
+        assert_eq!(
+            ts.truncate_to(Duration::from_secs(2)),
+            statx_timestamp::new(2, 0).unwrap()
+        );
+
+        let before_epoch = statx_timestamp::new(-3, 500_000_000).unwrap();
+        assert_eq!(
+            before_epoch.truncate_to(Duration::from_secs(2)),
+            statx_timestamp::new(-4, 0).unwrap()
+        );
+        assert_eq!(ts.truncate_to(Duration::from_secs(0)), ts);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_time() {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let ts = statx_timestamp::new(-2, 500_000_000).unwrap();
+        let time = SystemTime::try_from(ts).unwrap();
+        assert_eq!(time, UNIX_EPOCH - Duration::from_millis(1500));
+        let back = statx_timestamp::try_from(time).unwrap();
+        assert_eq!((back.tv_sec, back.tc_nsec), (-2, 500_000_000));
+    }
+}
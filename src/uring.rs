@@ -0,0 +1,156 @@
+//! `IORING_OP_STATX` submissions through io_uring (since Linux 5.6).
+
+use crate::{statx, StatxError, StatxRequest, AT_EMPTY_PATH, AT_FDCWD};
+use io_uring::{opcode, squeue, types};
+use libc::{c_int, c_uint};
+use std::boxed::Box;
+use std::cell::UnsafeCell;
+use std::ffi::CString;
+use std::vec::Vec;
+use std::{io, mem};
+
+pub use io_uring::IoUring;
+
+/// A `statx()` operation for io_uring, owning its path and result buffer.
+///
+/// Both are heap allocated, so the operation may be moved while in flight, but
+/// it must not be dropped before its completion is reaped.
+pub struct StatxOp {
+    dirfd: c_int,
+    path: CString,
+    flags: c_int,
+    mask: c_uint,
+    buf: Box<UnsafeCell<statx>>,
+}
+
+impl StatxOp {
+    /// An operation on `path`, relative to the current working directory.
+    pub fn path(request: &StatxRequest, path: &[u8]) -> Result<StatxOp, StatxError> {
+        StatxOp::at(request, AT_FDCWD, path)
+    }
+
+    /// An operation on `path`, relative to the directory `dirfd`.
+    pub fn at(request: &StatxRequest, dirfd: c_int, path: &[u8]) -> Result<StatxOp, StatxError> {
+        let path = CString::new(path).map_err(|_| StatxError::InteriorNul)?;
+        Ok(StatxOp::new(
+            dirfd,
+            path,
+            request.flags_bits(),
+            request.mask_bits(),
+        ))
+    }
+
+    /// An operation on the open file `fd`, using `AT_EMPTY_PATH`.
+    pub fn fd(request: &StatxRequest, fd: c_int) -> StatxOp {
+        let flags = request.flags_bits() | AT_EMPTY_PATH as c_int;
+        StatxOp::new(fd, CString::default(), flags, request.mask_bits())
+    }
+
+    fn new(dirfd: c_int, path: CString, flags: c_int, mask: c_uint) -> StatxOp {
+        StatxOp {
+            dirfd,
+            path,
+            flags,
+            mask,
+            buf: Box::new(UnsafeCell::new(unsafe { mem::zeroed() })),
+        }
+    }
+
+    /// The submission queue entry of the operation.
+    ///
+    /// Pushing it is `unsafe`: the operation must then be kept alive until the
+    /// completion with the same `user_data` is reaped.
+    pub fn entry(&self) -> squeue::Entry {
+        opcode::Statx::new(
+            types::Fd(self.dirfd),
+            self.path.as_ptr(),
+            self.buf.get() as *mut types::statx,
+        )
+        .flags(self.flags)
+        .mask(self.mask)
+        .build()
+    }
+
+    /// Address of the result buffer, used as `user_data` by `submit_all`.
+    fn token(&self) -> u64 {
+        self.buf.get() as usize as u64
+    }
+
+    /// Map the `result` of the completion queue entry, like the synchronous
+    /// call would have returned.
+    pub fn complete(self, result: i32) -> Result<statx, StatxError> {
+        if result < 0 {
+            Err(StatxError::from_errno(-result))
+        } else {
+            Ok(self.buf.into_inner())
+        }
+    }
+}
+
+/// Submit all operations to `ring` and wait for their completions.
+///
+/// Results are in the order of `ops`. The `user_data` of each entry is the
+/// address of the result buffer of its operation, which is unique as long as
+/// the operation is alive. Completions with another `user_data` are skipped.
+///
+/// On an error of the ring itself, operations still in flight are leaked, so
+/// that the kernel never writes to freed memory. Their completions may then
+/// show up in later calls, where they are skipped as well.
+///
+/// # Safety
+///
+/// No operation other than those leaked by previous calls may be in flight on
+/// `ring`, as its `user_data` could be mistaken for one of `ops`, whose
+/// buffers would then be freed while the kernel still writes to them.
+pub unsafe fn submit_all(
+    ring: &mut IoUring,
+    ops: Vec<StatxOp>,
+) -> io::Result<Vec<Result<statx, StatxError>>> {
+    let mut tokens: Vec<(u64, usize)> = ops
+        .iter()
+        .enumerate()
+        .map(|(i, op)| (op.token(), i))
+        .collect();
+    tokens.sort_unstable();
+    let mut results: Vec<Option<i32>> = ops.iter().map(|_| None).collect();
+    let (mut next, mut pending) = (0, ops.len());
+
+    while pending > 0 {
+        {
+            let mut sq = ring.submission();
+            while next < ops.len() && !sq.is_full() {
+                let entry = ops[next].entry().user_data(ops[next].token());
+                // The operation is kept alive below until completed.
+                unsafe { sq.push(&entry) }.expect("submission queue is full");
+                next += 1;
+            }
+        }
+
+        match ring.submit_and_wait(1) {
+            Ok(_) => {}
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => {
+                mem::forget(ops);
+                return Err(err);
+            }
+        }
+
+        for cqe in ring.completion() {
+            let index = match tokens.binary_search_by_key(&cqe.user_data(), |&(token, _)| token) {
+                Ok(pos) => tokens[pos].1,
+                Err(_) => continue,
+            };
+            // Only count the first completion of an operation pushed above.
+            if index < next && results[index].is_none() {
+                results[index] = Some(cqe.result());
+                pending -= 1;
+            }
+        }
+    }
+
+    Ok(ops
+        .into_iter()
+        .zip(results)
+        .map(|(op, result)| op.complete(result.expect("every operation is completed")))
+        .collect())
+}
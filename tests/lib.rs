@@ -34,3 +34,341 @@ fn test_stat() {
         println!("statx() success: {:?}", buf.into_inner());
     }
 }
+
+#[test]
+#[ignore]
+fn test_statx_path() {
+    use std::ffi::CString;
+
+    let buf = statx_path(&CString::new(".").unwrap(), 0, STATX_ALL).unwrap();
+    assert_ne!(buf.stx_mask & STATX_BASIC_STATS, 0);
+
+    let err = statx_path(&CString::new("./does/not/exist").unwrap(), 0, STATX_ALL);
+    assert_eq!(err.unwrap_err(), StatxError::NotFound);
+}
+
+#[test]
+#[ignore]
+fn test_request() {
+    use std::ffi::CString;
+    use std::fs::File;
+    use std::os::unix::io::AsRawFd;
+
+    let by_path = StatxRequest::new()
+        .mask(StatxMask::BASIC_STATS | StatxMask::BTIME)
+        .sync(SyncMode::DontSync)
+        .path(&CString::new("Cargo.toml").unwrap())
+        .unwrap();
+    let file = File::open("Cargo.toml").unwrap();
+    let by_fd = StatxRequest::new().fd(file.as_raw_fd()).unwrap();
+    assert_eq!(by_path.stx_ino, by_fd.stx_ino);
+}
+
+#[test]
+#[ignore]
+fn test_mnt_id() {
+    use std::ffi::CString;
+
+    let buf = StatxRequest::new()
+        .mask(StatxMask::MNT_ID)
+        .path(&CString::new("/").unwrap())
+        .unwrap();
+    assert!(buf.mnt_id().is_some());
+}
+
+#[test]
+#[ignore]
+fn test_from_stat() {
+    use std::ffi::CString;
+    use std::mem::zeroed;
+
+    let path = CString::new("Cargo.toml").unwrap();
+    let mut st = unsafe { zeroed::<libc::stat>() };
+    assert_eq!(unsafe { libc::stat(path.as_ptr(), &mut st) }, 0);
+    let from_stat = statx::from_stat(&st);
+    let buf = statx_path(&path, 0, STATX_BASIC_STATS).unwrap();
+
+    assert_eq!(from_stat.mask(), StatxMask::BASIC_STATS);
+    assert_eq!(from_stat.ino(), buf.ino());
+    assert_eq!(from_stat.size(), buf.size());
+    assert_eq!(from_stat.mode(), buf.mode());
+    assert_eq!(from_stat.stx_mtime.tv_sec, buf.stx_mtime.tv_sec);
+    assert_eq!(from_stat.stx_mtime.tc_nsec, buf.stx_mtime.tc_nsec);
+    assert_eq!(
+        (from_stat.stx_dev_major, from_stat.stx_dev_minor),
+        (buf.stx_dev_major, buf.stx_dev_minor)
+    );
+}
+
+#[test]
+#[ignore]
+fn test_kernel_support() {
+    use std::ffi::CString;
+
+    let support = kernel_support();
+    assert!(support.available);
+    assert!(support.mask.contains(StatxMask::ALL));
+    assert_eq!(kernel_support(), support);
+
+    // Bits actually returned are never missing.
+    let all = StatxRequest::new().mask(!StatxMask::empty());
+    let root = all.path(&CString::new("/").unwrap()).unwrap();
+    assert!(support.mask.contains(root.mask()));
+}
+
+#[test]
+#[ignore]
+fn test_dev() {
+    use std::ffi::CString;
+    use std::os::unix::fs::MetadataExt;
+
+    let meta = std::fs::metadata("Cargo.toml").unwrap();
+    let buf = statx_path(&CString::new("Cargo.toml").unwrap(), 0, STATX_BASIC_STATS).unwrap();
+    assert_eq!(buf.dev(), meta.dev());
+    assert_eq!(buf.rdev(), meta.rdev());
+    assert_eq!(DeviceId::from_dev(meta.dev()), buf.dev_id());
+}
+
+#[test]
+#[ignore]
+fn test_to_stat() {
+    use std::ffi::CString;
+    use std::mem::zeroed;
+
+    let path = CString::new("Cargo.toml").unwrap();
+    let mut st = unsafe { zeroed::<libc::stat>() };
+    assert_eq!(unsafe { libc::stat(path.as_ptr(), &mut st) }, 0);
+    let buf = statx_path(&path, 0, STATX_BASIC_STATS).unwrap();
+    let emulated = buf.to_stat().unwrap();
+
+    assert_eq!(emulated.st_dev, st.st_dev);
+    assert_eq!(emulated.st_ino, st.st_ino);
+    assert_eq!(emulated.st_mode, st.st_mode);
+    assert_eq!(emulated.st_nlink, st.st_nlink);
+    assert_eq!(emulated.st_size, st.st_size);
+    assert_eq!(emulated.st_blocks, st.st_blocks);
+    assert_eq!(emulated.st_blksize, st.st_blksize);
+    assert_eq!(emulated.st_mtime, st.st_mtime);
+    assert_eq!(emulated.st_mtime_nsec, st.st_mtime_nsec);
+}
+
+#[test]
+#[ignore]
+#[cfg(feature = "std")]
+fn test_statx_fd() {
+    use std::fs::File;
+
+    let file = File::open("Cargo.toml").unwrap();
+    let buf = statx_fd(&file, StatxMask::BASIC_STATS).unwrap();
+    assert_eq!(buf.size(), Some(file.metadata().unwrap().len()));
+}
+
+#[test]
+#[ignore]
+fn test_dir() {
+    use std::ffi::CString;
+    use std::fs;
+
+    let root = std::env::temp_dir().join(format!("statx-sys-test-dir-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("a/b")).unwrap();
+    fs::write(root.join("a/file"), b"hello").unwrap();
+    std::os::unix::fs::symlink("file", root.join("a/link")).unwrap();
+
+    let c = |s: &str| CString::new(s).unwrap();
+    let dir = Dir::open(&c(root.join("a").to_str().unwrap())).unwrap();
+
+    // Lookups follow the directory, not its path.
+    fs::rename(root.join("a"), root.join("renamed")).unwrap();
+    assert_eq!(dir.stat(&c("file")).unwrap().size(), Some(5));
+    assert_eq!(dir.stat(&c("link")).unwrap().size(), Some(5));
+    assert_eq!(
+        dir.lstat(&c("link")).unwrap().file_type(),
+        Some(FileType::Symlink)
+    );
+
+    let sub = dir.open_subdir(&c("b")).unwrap();
+    let sub_stat = sub.stat_self(&StatxRequest::new()).unwrap();
+    assert_eq!(sub_stat.file_type(), Some(FileType::Directory));
+    assert_eq!(
+        dir.open_subdir(&c("link")).unwrap_err(),
+        StatxError::NotADirectory
+    );
+
+    // Only single components are looked up.
+    for name in &["../renamed/file", "b/."] {
+        let err = dir.stat(&c(name)).unwrap_err();
+        assert_eq!(err, StatxError::InvalidArgument);
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_statx_bytes() {
+    let buf = statx_bytes(b"Cargo.toml", 0, STATX_BASIC_STATS).unwrap();
+    assert_eq!(buf.file_type(), Some(FileType::Regular));
+    let err = StatxRequest::new().path_bytes(b"Cargo.toml\0.bak");
+    assert_eq!(err.unwrap_err(), StatxError::InteriorNul);
+}
+
+#[test]
+#[ignore]
+#[cfg(feature = "io-uring")]
+fn test_uring() {
+    use statx_sys::uring::{submit_all, StatxOp};
+
+    let request = StatxRequest::new().mask(StatxMask::BASIC_STATS | StatxMask::BTIME);
+    let paths: &[&[u8]] = &[b"Cargo.toml", b"src", b"does-not-exist", b"src/lib.rs"];
+    let ops = paths
+        .iter()
+        .map(|path| StatxOp::path(&request, path).unwrap())
+        .collect();
+
+    let mut ring = io_uring::IoUring::new(2).unwrap();
+    // A completion which is not from `submit_all`, skipped.
+    let nop = io_uring::opcode::Nop::new().build().user_data(1);
+    unsafe { ring.submission().push(&nop) }.unwrap();
+    let results = unsafe { submit_all(&mut ring, ops) }.unwrap();
+    for (path, result) in paths.iter().zip(results) {
+        match request.path_bytes(path) {
+            Ok(expected) => assert_eq!(result.unwrap().ino(), expected.ino()),
+            Err(err) => assert_eq!(result.unwrap_err(), err),
+        }
+    }
+}
+
+#[test]
+#[ignore]
+#[cfg(feature = "std")]
+fn test_statx_many() {
+    let mut paths: Vec<String> = (0..100).map(|_| "Cargo.toml".to_owned()).collect();
+    paths[42] = "does-not-exist".to_owned();
+    paths.push("src".to_owned());
+
+    let request = StatxRequest::new();
+    for &threads in &[1, 4] {
+        let results = statx_many_with_threads(&paths, &request, threads);
+        assert_eq!(results.len(), paths.len());
+        assert_eq!(results[0].unwrap().file_type(), Some(FileType::Regular));
+        assert_eq!(results[42].unwrap_err(), StatxError::NotFound);
+        assert_eq!(results[100].unwrap().file_type(), Some(FileType::Directory));
+    }
+    assert_eq!(statx_many(&paths, &request).len(), paths.len());
+
+    let mut out = statx_many(&paths, &request);
+    paths.swap(0, 42);
+    statx_many_into(&paths, &request, &mut out);
+    assert_eq!(out[0].unwrap_err(), StatxError::NotFound);
+    assert_eq!(out[42].unwrap().file_type(), Some(FileType::Regular));
+    assert_eq!(out[100].unwrap().file_type(), Some(FileType::Directory));
+}
+
+#[cfg(feature = "async")]
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+
+    struct Unpark(std::thread::Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Arc::new(Unpark(std::thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+/// Spawner dropping the tasks without running them.
+#[cfg(feature = "async")]
+fn drop_task(task: Box<dyn FnOnce() + Send>) {
+    drop(task);
+}
+
+#[test]
+#[ignore]
+#[cfg(feature = "async")]
+fn test_future() {
+    use statx_sys::future::statx_with;
+
+    let spawner = |task: Box<dyn FnOnce() + Send>| {
+        std::thread::spawn(task);
+    };
+    for &fallback in &[false, true] {
+        let request = StatxRequest::new().fallback(fallback);
+        let buf = block_on(statx_with(&spawner, "Cargo.toml", request)).unwrap();
+        assert_eq!(buf.file_type(), Some(FileType::Regular));
+        let err = block_on(statx_with(&spawner, "does-not-exist", request));
+        assert_eq!(err.unwrap_err(), StatxError::NotFound);
+    }
+}
+
+#[test]
+#[ignore]
+#[cfg(all(feature = "async", not(feature = "io-uring")))]
+fn test_future_cancelled() {
+    use statx_sys::future::statx_with;
+
+    let err = block_on(statx_with(&drop_task, "src", StatxRequest::new()));
+    assert_eq!(err.unwrap_err(), StatxError::Other(libc::ECANCELED));
+}
+
+#[test]
+#[ignore]
+#[cfg(all(feature = "async", feature = "io-uring"))]
+fn test_future_uring() {
+    use statx_sys::future::statx_with;
+
+    // Through the ring, the spawner is not used.
+    let futures: Vec<_> = (0..100)
+        .map(|_| statx_with(&drop_task, "src", StatxRequest::new()))
+        .collect();
+    for future in futures {
+        let buf = block_on(future).unwrap();
+        assert_eq!(buf.file_type(), Some(FileType::Directory));
+    }
+
+    // Requests with a fallback take the blocking path.
+    let request = StatxRequest::new().fallback(true);
+    let err = block_on(statx_with(&drop_task, "src", request));
+    assert_eq!(err.unwrap_err(), StatxError::Other(libc::ECANCELED));
+}
+
+#[test]
+#[ignore]
+#[cfg(feature = "tokio")]
+fn test_tokio() {
+    use statx_sys::future::tokio_statx;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let buf = runtime
+        .block_on(tokio_statx("Cargo.toml", StatxRequest::new()))
+        .unwrap();
+    assert_eq!(buf.file_type(), Some(FileType::Regular));
+}
+
+#[test]
+#[ignore]
+#[cfg(feature = "serde")]
+fn test_serde() {
+    let request = StatxRequest::new().mask(StatxMask::ALL | StatxMask::MNT_ID);
+    let buf = request.path_bytes(b"Cargo.toml").unwrap();
+    let json = serde_json::to_string(&buf).unwrap();
+    let back: statx = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.mask(), buf.mask());
+    assert_eq!(back.size(), buf.size());
+    assert_eq!(back.mtime(), buf.mtime());
+    assert_eq!(serde_json::to_string(&back).unwrap(), json);
+}